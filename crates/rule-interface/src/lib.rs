@@ -0,0 +1,314 @@
+//! Interface a rule program implements to govern lazorkit smart wallets.
+//!
+//! Lazorkit invokes the following instructions on a wallet's rule program, always signing
+//! for the smart wallet authenticator that authorized the operation:
+//!
+//! - `init_rule`: the wallet adopts the rule
+//! - `check_rule`: approve an instruction the wallet is about to execute, described by
+//!   [`CheckRuleArgs`] which lazorkit builds itself
+//! - `destroy`: the wallet leaves the rule
+//! - `revoke_authenticator`: an authenticator is removed from the wallet
+//! - `post_check`: optional, inspect what an approved instruction actually did. Lazorkit
+//!   only calls it when `check_rule` returned a [`PostCheckRequest`], passing the balance
+//!   changes described by [`PostCheckArgs`]
+//!
+//! Each instruction's accounts start with the fixed prefix given by
+//! [`RuleInstruction::layout`], followed by any rule-specific accounts. Rule-specific
+//! arguments follow the discriminator, except for `check_rule` and `post_check`.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::set_return_data},
+};
+
+/// Instruction discriminators of the rule interface, `sha256("global:<name>")[..8]`
+pub mod discriminator {
+    pub const INIT_RULE: [u8; 8] = [129, 224, 96, 169, 247, 125, 74, 118];
+    pub const CHECK_RULE: [u8; 8] = [215, 90, 220, 175, 191, 212, 144, 147];
+    pub const DESTROY: [u8; 8] = [157, 40, 96, 3, 135, 203, 143, 74];
+    pub const REVOKE_AUTHENTICATOR: [u8; 8] = [160, 188, 112, 177, 74, 27, 228, 228];
+    pub const POST_CHECK: [u8; 8] = [1, 209, 151, 19, 54, 31, 216, 229];
+}
+
+/// Instructions lazorkit invokes on a rule program
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleInstruction {
+    InitRule,
+    CheckRule,
+    Destroy,
+    RevokeAuthenticator,
+    PostCheck,
+}
+
+/// Role of an account in the fixed prefix of a rule instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleAccount {
+    /// Writable signer paying for any account the rule creates
+    Payer,
+    /// Smart wallet PDA, writable where it receives the rent of closed accounts
+    SmartWallet,
+    /// Authenticator that authorized the operation, signed for by lazorkit
+    SmartWalletAuthenticator,
+    /// Authenticator being removed from the wallet
+    RevokedAuthenticator,
+}
+
+impl RuleInstruction {
+    pub const ALL: [Self; 5] = [
+        Self::InitRule,
+        Self::CheckRule,
+        Self::Destroy,
+        Self::RevokeAuthenticator,
+        Self::PostCheck,
+    ];
+
+    pub const fn discriminator(self) -> [u8; 8] {
+        match self {
+            Self::InitRule => discriminator::INIT_RULE,
+            Self::CheckRule => discriminator::CHECK_RULE,
+            Self::Destroy => discriminator::DESTROY,
+            Self::RevokeAuthenticator => discriminator::REVOKE_AUTHENTICATOR,
+            Self::PostCheck => discriminator::POST_CHECK,
+        }
+    }
+
+    /// Fixed accounts the instruction's account list starts with
+    pub const fn layout(self) -> &'static [RuleAccount] {
+        use RuleAccount::*;
+        match self {
+            Self::InitRule => &[Payer, SmartWallet, SmartWalletAuthenticator],
+            Self::CheckRule | Self::PostCheck => &[SmartWalletAuthenticator],
+            Self::Destroy => &[SmartWallet, SmartWalletAuthenticator],
+            Self::RevokeAuthenticator => &[SmartWalletAuthenticator, RevokedAuthenticator],
+        }
+    }
+
+    /// Identify the rule instruction encoded by `data`
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|ix| data.get(..8) == Some(&ix.discriminator()))
+    }
+
+    /// Instruction data of this instruction with rule-specific `args`
+    pub fn data(self, args: &impl AnchorSerialize) -> Vec<u8> {
+        let mut data = self.discriminator().to_vec();
+        args.serialize(&mut data)
+            .expect("serializing to a Vec cannot fail");
+        data
+    }
+
+    /// Whether `keys` start with the fixed accounts of this instruction, where `expected`
+    /// gives the key required for each role, if any
+    pub fn matches_layout(
+        self,
+        keys: &[Pubkey],
+        expected: impl Fn(RuleAccount) -> Option<Pubkey>,
+    ) -> bool {
+        let layout = self.layout();
+        keys.len() >= layout.len()
+            && layout
+                .iter()
+                .zip(keys)
+                .all(|(role, key)| expected(*role).is_none_or(|expected| expected == *key))
+    }
+}
+
+/// Account meta of an instruction submitted to a rule program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RuleAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Arguments of a rule program's `check_rule`: the instruction the smart wallet is about
+/// to execute, exactly as lazorkit will invoke it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CheckRuleArgs {
+    pub program_id: Pubkey,
+    pub accounts: Vec<RuleAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl CheckRuleArgs {
+    /// Instruction data of the `check_rule` call judging these arguments
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        RuleInstruction::CheckRule.data(self)
+    }
+
+    /// Parse the arguments back from `check_rule` instruction data
+    pub fn try_from_instruction_data(data: &[u8]) -> Option<Self> {
+        match RuleInstruction::parse(data)? {
+            RuleInstruction::CheckRule => Self::try_from_slice(&data[8..]).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Instruction> for CheckRuleArgs {
+    fn from(ix: &Instruction) -> Self {
+        Self {
+            program_id: ix.program_id,
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|meta| RuleAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: ix.data.clone(),
+        }
+    }
+}
+
+/// Return data of a `check_rule` asking lazorkit to call `post_check` once the approved
+/// instruction has run
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PostCheckRequest {
+    /// Token accounts whose amounts the rule wants to see before and after
+    pub token_accounts: Vec<Pubkey>,
+}
+
+impl PostCheckRequest {
+    /// Hand the request back to lazorkit, to be called from `check_rule`
+    pub fn set_return_data(&self) {
+        set_return_data(&self.try_to_vec().expect("serializing to a Vec cannot fail"));
+    }
+}
+
+/// Balance of an account before and after the approved instruction ran
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub before: u64,
+    pub after: u64,
+}
+
+impl BalanceChange {
+    /// Amount the balance decreased by, zero if it did not decrease
+    pub fn outflow(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+/// Amount of a requested token account before and after the approved instruction ran
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TokenAccountChange {
+    pub account: Pubkey,
+    pub amount: BalanceChange,
+}
+
+/// Arguments of a rule program's `post_check`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PostCheckArgs {
+    /// Lamports of the smart wallet
+    pub lamports: BalanceChange,
+    /// The token accounts of the [`PostCheckRequest`], in the requested order
+    pub token_accounts: Vec<TokenAccountChange>,
+}
+
+impl PostCheckArgs {
+    /// Instruction data of the `post_check` call receiving these arguments
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        RuleInstruction::PostCheck.data(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::{hash::hash, instruction::AccountMeta};
+
+    #[test]
+    fn discriminators_match_anchor_sighash() {
+        for (ix, name) in RuleInstruction::ALL.into_iter().zip([
+            "init_rule",
+            "check_rule",
+            "destroy",
+            "revoke_authenticator",
+            "post_check",
+        ]) {
+            let sighash = hash(format!("global:{name}").as_bytes()).to_bytes();
+            assert_eq!(ix.discriminator(), sighash[..8], "{name}");
+        }
+    }
+
+    #[test]
+    fn parse_identifies_instructions() {
+        for ix in RuleInstruction::ALL {
+            assert_eq!(RuleInstruction::parse(&ix.data(&42u64)), Some(ix));
+        }
+        assert_eq!(RuleInstruction::parse(&[0; 8]), None);
+        assert_eq!(RuleInstruction::parse(&discriminator::DESTROY[..7]), None);
+    }
+
+    #[test]
+    fn check_rule_args_round_trip() {
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0],
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+        );
+        let args = CheckRuleArgs::from(&ix);
+        assert_eq!(args.accounts[0].pubkey, ix.accounts[0].pubkey);
+        assert!(args.accounts[0].is_signer && !args.accounts[1].is_writable);
+        assert_eq!(
+            CheckRuleArgs::try_from_instruction_data(&args.to_instruction_data()),
+            Some(args)
+        );
+        assert_eq!(
+            CheckRuleArgs::try_from_instruction_data(&RuleInstruction::Destroy.data(&())),
+            None
+        );
+    }
+
+    #[test]
+    fn post_check_args_round_trip() {
+        let args = PostCheckArgs {
+            lamports: BalanceChange {
+                before: 10,
+                after: 4,
+            },
+            token_accounts: vec![TokenAccountChange {
+                account: Pubkey::new_unique(),
+                amount: BalanceChange {
+                    before: 1,
+                    after: 3,
+                },
+            }],
+        };
+        assert_eq!(args.lamports.outflow(), 6);
+        assert_eq!(args.token_accounts[0].amount.outflow(), 0);
+
+        let data = args.to_instruction_data();
+        assert_eq!(
+            RuleInstruction::parse(&data),
+            Some(RuleInstruction::PostCheck)
+        );
+        assert_eq!(PostCheckArgs::try_from_slice(&data[8..]).unwrap(), args);
+    }
+
+    #[test]
+    fn matches_layout_checks_fixed_accounts() {
+        let (wallet, authenticator, other) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let expected = |role| match role {
+            RuleAccount::SmartWallet => Some(wallet),
+            RuleAccount::SmartWalletAuthenticator => Some(authenticator),
+            _ => None,
+        };
+        let destroy = RuleInstruction::Destroy;
+        assert!(destroy.matches_layout(&[wallet, authenticator, other], expected));
+        assert!(!destroy.matches_layout(&[authenticator, wallet], expected));
+        assert!(!destroy.matches_layout(&[wallet], expected));
+        assert!(RuleInstruction::InitRule.matches_layout(&[other, wallet, authenticator], expected));
+    }
+}
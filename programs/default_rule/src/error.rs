@@ -5,4 +5,10 @@ pub enum RuleError {
     InvalidPasskey,
 
     UnAuthorize,
+
+    AlreadyInitialized,
+
+    InvalidAuthority,
+
+    InvalidUpgradeAuthority,
 }
@@ -9,7 +9,8 @@ pub fn destroy(_ctx: Context<Destroy>) -> Result<()> {
 
 #[derive(Accounts)]
 pub struct Destroy<'info> {
-    /// CHECK
+    #[account(mut)]
+    /// CHECK: Receives the rent of the closed rule account
     pub smart_wallet: UncheckedAccount<'info>,
     /// CHECK
     pub smart_wallet_authenticator: Signer<'info>,
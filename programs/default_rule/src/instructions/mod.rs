@@ -2,6 +2,7 @@ mod check_rule;
 mod destroy;
 mod init_rule;
 mod initialize;
+mod revoke_authenticator;
 
 pub use init_rule::*;
 
@@ -10,3 +11,5 @@ pub use initialize::*;
 pub use check_rule::*;
 
 pub use destroy::*;
+
+pub use revoke_authenticator::*;
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::RuleError, state::Rule, ID};
+
+pub fn revoke_authenticator(_ctx: Context<RevokeAuthenticator>) -> Result<()> {
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevokeAuthenticator<'info> {
+    pub smart_wallet_authenticator: Signer<'info>,
+
+    /// CHECK: Authenticator being revoked by lazorkit
+    #[account(
+        constraint = revoked_authenticator.key() != rule.admin @ RuleError::UnAuthorize,
+    )]
+    pub revoked_authenticator: UncheckedAccount<'info>,
+
+    #[account(
+        owner = ID,
+        constraint = smart_wallet_authenticator.key() == rule.admin @ RuleError::UnAuthorize,
+    )]
+    pub rule: Account<'info, Rule>,
+}
@@ -1,4 +1,8 @@
+// Anchor's `#[program]` expansion still calls the deprecated `AccountInfo::realloc`.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+use rule_interface::{discriminator, CheckRuleArgs};
 
 declare_id!("B98ooLRYBP6m6Zsrd3Hnzn4UAejfVZwyDgMFaBNzVR2W");
 
@@ -17,15 +21,23 @@ pub mod default_rule {
         instructions::initialize(ctx, lazorkit_author)
     }
 
+    #[instruction(discriminator = &discriminator::INIT_RULE)]
     pub fn init_rule(ctx: Context<InitRule>) -> Result<()> {
         instructions::init_rule(ctx)
     }
 
-    pub fn check_rule(_ctx: Context<CheckRule>) -> Result<()> {
+    #[instruction(discriminator = &discriminator::CHECK_RULE)]
+    pub fn check_rule(_ctx: Context<CheckRule>, _args: CheckRuleArgs) -> Result<()> {
         instructions::check_rule(_ctx)
     }
 
+    #[instruction(discriminator = &discriminator::DESTROY)]
     pub fn destroy(ctx: Context<Destroy>) -> Result<()> {
         instructions::destroy(ctx)
     }
+
+    #[instruction(discriminator = &discriminator::REVOKE_AUTHENTICATOR)]
+    pub fn revoke_authenticator(ctx: Context<RevokeAuthenticator>) -> Result<()> {
+        instructions::revoke_authenticator(ctx)
+    }
 }
@@ -2,12 +2,15 @@ use anchor_lang::prelude::*;
 
 /// Program IDs
 pub const SECP256R1_ID: Pubkey = pubkey!("Secp256r1SigVerify1111111111111111111111111");
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
 
 /// Seeds for PDA derivation
 pub const SMART_WALLET_SEED: &[u8] = b"smart_wallet";
 
 /// Size constants for account data
 pub const PASSKEY_SIZE: usize = 33; // Secp256r1 compressed pubkey size
+pub const TOKEN_ACCOUNT_SIZE: usize = 165; // Base SPL token account size
 
 pub const AUTHORITY_SEED: &[u8] = b"authority";
 
@@ -27,4 +27,68 @@ pub enum LazorKitError {
 
     #[msg("Invalid rule instruction provided")]
     InvalidRuleInstruction,
+
+    /// Replay protection errors
+    #[msg("Nonce does not match the smart wallet's expected nonce")]
+    InvalidNonce,
+    #[msg("Signed message has expired")]
+    MessageExpired,
+
+    #[msg("Account is already in the current layout")]
+    AccountAlreadyMigrated,
+
+    /// WebAuthn assertion errors
+    #[msg("Invalid WebAuthn authenticator data")]
+    InvalidAuthenticatorData,
+    #[msg("Invalid WebAuthn client data JSON")]
+    InvalidClientData,
+    #[msg("Client data challenge does not match the signed message")]
+    ChallengeMismatch,
+    #[msg("Authenticator rpIdHash does not match the relying party")]
+    RpIdHashMismatch,
+    #[msg("Authenticator did not report user presence")]
+    UserNotPresent,
+
+    /// Relying party errors
+    #[msg("Client data origin is not an allowed relying party")]
+    OriginNotAllowed,
+
+    #[msg("Relying party set is empty, too large or has an invalid origin")]
+    InvalidRelyingParties,
+
+    #[msg("Action requires user verification")]
+    UserNotVerified,
+
+    #[msg("Authenticator signature counter did not increase")]
+    SignCountRegression,
+
+    #[msg("Signature s value is not in the lower half of the curve order")]
+    HighSSignature,
+
+    #[msg("The last authenticator of a smart wallet cannot be revoked")]
+    LastAuthenticator,
+
+    /// Admin errors
+    #[msg("Signer is not the protocol admin")]
+    Unauthorized,
+
+    #[msg("Rule program is not whitelisted")]
+    RuleProgramNotWhitelisted,
+
+    #[msg("Program has already been initialized")]
+    AlreadyInitialized,
+
+    /// Rule program list errors
+    #[msg("Rule program is already attached to the smart wallet")]
+    RuleProgramAlreadyAttached,
+    #[msg("Smart wallet already has the maximum number of rule programs")]
+    TooManyRulePrograms,
+    #[msg("The last rule program of a smart wallet cannot be removed")]
+    LastRuleProgram,
+
+    #[msg("Account is not a token account")]
+    InvalidTokenAccount,
+
+    #[msg("Signer is not the program's upgrade authority")]
+    InvalidUpgradeAuthority,
 }
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::ConfigUpdate;
+
+/// Emitted for every change made through `update_config`
+#[event]
+pub struct ConfigUpdated {
+    pub update: ConfigUpdate,
+}
+
+/// Emitted when a passkey reports a signature counter that did not increase, which
+/// indicates the authenticator may have been cloned. The authenticator is flagged and can
+/// no longer be used
+#[event]
+pub struct SignCountRegression {
+    pub smart_wallet: Pubkey,
+    pub smart_wallet_authenticator: Pubkey,
+    pub stored_sign_count: u32,
+    pub received_sign_count: u32,
+}
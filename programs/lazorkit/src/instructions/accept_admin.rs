@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::LazorKitError, state::Config, ID};
+
+/// Second step of an admin transfer: the proposed admin takes over
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.pending_admin.key();
+    config.pending_admin = None;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Config::PREFIX_SEED],
+        bump,
+        owner = ID,
+        constraint = config.pending_admin == Some(pending_admin.key()) @ LazorKitError::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
@@ -1,11 +1,12 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program};
 
 use crate::{
     constants::{PASSKEY_SIZE, SMART_WALLET_SEED},
     state::{
-        Config, SmartWalletAuthenticator, SmartWalletConfig, SmartWalletSeq, WhitelistRulePrograms,
+        Config, RelyingParty, SmartWalletAuthenticator, SmartWalletConfig, SmartWalletSeq,
+        VerificationPolicy, WhitelistRulePrograms,
     },
-    utils::{execute_cpi, transfer_sol_from_pda, PasskeyExt, PdaSigner},
+    utils::{execute_cpi, PasskeyExt, PdaSigner},
     ID,
 };
 
@@ -13,14 +14,24 @@ pub fn create_smart_wallet(
     ctx: Context<CreateSmartWallet>,
     passkey_pubkey: [u8; PASSKEY_SIZE],
     rule_data: Vec<u8>,
+    relying_parties: Option<Vec<RelyingParty>>,
 ) -> Result<()> {
+    if let Some(relying_parties) = &relying_parties {
+        RelyingParty::validate_set(relying_parties, SmartWalletConfig::MAX_RELYING_PARTIES)?;
+    }
+
     let wallet_data = &mut ctx.accounts.smart_wallet_config;
     let sequence_account = &mut ctx.accounts.smart_wallet_seq;
     let smart_wallet_authenticator = &mut ctx.accounts.smart_wallet_authenticator;
 
     wallet_data.set_inner(SmartWalletConfig {
-        rule_program: ctx.accounts.config.default_rule_program,
+        version: SmartWalletConfig::VERSION,
+        rule_programs: vec![ctx.accounts.config.default_rule_program],
         id: sequence_account.seq,
+        nonce: 0,
+        authenticator_count: 1,
+        relying_parties,
+        verification_policy: VerificationPolicy::default(),
         bump: ctx.bumps.smart_wallet,
     });
 
@@ -28,6 +39,8 @@ pub fn create_smart_wallet(
     smart_wallet_authenticator.set_inner(SmartWalletAuthenticator {
         passkey_pubkey,
         smart_wallet: ctx.accounts.smart_wallet.key(),
+        sign_count: 0,
+        clone_suspected: false,
         bump: ctx.bumps.smart_wallet_authenticator,
     });
 
@@ -39,7 +52,7 @@ pub fn create_smart_wallet(
     };
 
     execute_cpi(
-        &ctx.remaining_accounts,
+        ctx.remaining_accounts,
         rule_data,
         &ctx.accounts.default_rule_program,
         Some(signer),
@@ -47,11 +60,19 @@ pub fn create_smart_wallet(
 
     sequence_account.seq += 1;
 
-    transfer_sol_from_pda(
-        &ctx.accounts.smart_wallet,
-        &mut ctx.accounts.signer,
-        ctx.accounts.config.create_smart_wallet_fee,
-    )?;
+    // --- Protocol creation fee, paid by the signer funding the wallet ---
+    if ctx.accounts.config.create_smart_wallet_fee > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.signer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            ctx.accounts.config.create_smart_wallet_fee,
+        )?;
+    }
 
     Ok(())
 }
@@ -112,6 +133,13 @@ pub struct CreateSmartWallet<'info> {
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+        mut,
+        address = config.treasury,
+    )]
+    /// CHECK: Receives the creation fee.
+    pub treasury: UncheckedAccount<'info>,
+
     #[account(
         address = config.default_rule_program
     )]
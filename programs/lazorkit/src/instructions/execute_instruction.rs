@@ -1,17 +1,32 @@
-use anchor_lang::{prelude::*, solana_program::sysvar::instructions::load_instruction_at_checked};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        hash::hash, instruction::Instruction, program::get_return_data,
+        sysvar::instructions::load_instruction_at_checked,
+    },
+};
 
+use crate::events::SignCountRegression;
+use crate::message::ExecuteMessage;
 use crate::state::Config;
 use crate::utils::{
-    check_whitelist, execute_cpi, get_pda_signer, sighash, transfer_sol_from_pda,
-    verify_secp256r1_instruction, PasskeyExt, PdaSigner,
+    check_whitelist, create_cpi_instruction, execute_cpi, get_pda_signer, token_account_amount,
+    transfer_sol_from_pda, verify_secp256r1_instruction, PasskeyExt, PdaSigner,
 };
 use crate::{
     constants::{SMART_WALLET_SEED, SOL_TRANSFER_DISCRIMINATOR},
     error::LazorKitError,
-    state::{SmartWalletAuthenticator, SmartWalletConfig, WhitelistRulePrograms},
-    ID,
+    state::{
+        RelyingParty, SmartWalletAuthenticator, SmartWalletConfig, VerificationPolicy,
+        WhitelistRulePrograms,
+    },
+    webauthn, ID,
 };
 use anchor_lang::solana_program::sysvar::instructions::ID as IX_ID;
+use rule_interface::{
+    BalanceChange, CheckRuleArgs, PostCheckArgs, PostCheckRequest, RuleAccount, RuleInstruction,
+    TokenAccountChange,
+};
 
 /// Enum for supported actions in the instruction
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
@@ -21,6 +36,47 @@ pub enum Action {
     ChangeProgramRule,
     CheckAuthenticator,
     CallRuleProgram,
+    UpdateVerificationPolicy,
+    ExecuteBatch,
+    RevokeAuthenticator,
+    AddRuleProgram,
+    RemoveRuleProgram,
+    UpdateRelyingParties,
+}
+
+impl Action {
+    /// Bit identifying the action in a [`VerificationPolicy`] mask
+    pub const fn mask(&self) -> u16 {
+        match self {
+            Action::ExecuteCpi => 1 << 0,
+            Action::ChangeProgramRule => 1 << 1,
+            Action::CheckAuthenticator => 1 << 2,
+            Action::CallRuleProgram => 1 << 3,
+            Action::UpdateVerificationPolicy => 1 << 4,
+            Action::ExecuteBatch => 1 << 5,
+            Action::RevokeAuthenticator => 1 << 6,
+            Action::AddRuleProgram => 1 << 7,
+            Action::RemoveRuleProgram => 1 << 8,
+            Action::UpdateRelyingParties => 1 << 9,
+        }
+    }
+}
+
+/// Point in time after which a signed message can no longer be executed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum Expiry {
+    Slot(u64),
+    UnixTimestamp(i64),
+}
+
+impl Expiry {
+    /// Whether the expiry has passed according to the given clock
+    pub fn is_expired(&self, clock: &Clock) -> bool {
+        match *self {
+            Expiry::Slot(slot) => clock.slot > slot,
+            Expiry::UnixTimestamp(timestamp) => clock.unix_timestamp > timestamp,
+        }
+    }
 }
 
 /// Arguments for the execute_instruction entrypoint
@@ -28,12 +84,21 @@ pub enum Action {
 pub struct ExecuteInstructionArgs {
     pub passkey_pubkey: [u8; 33],
     pub signature: Vec<u8>,
-    pub message: Vec<u8>,
+    pub authenticator_data: Vec<u8>,
+    pub client_data_json: Vec<u8>,
     pub verify_instruction_index: u8,
     pub rule_data: CpiData,
+    pub rules: Vec<RuleAccounts>,
     pub cpi_data: Option<CpiData>,
+    pub batch: Vec<BatchCpiData>,
     pub action: Action,
     pub create_new_authenticator: Option<[u8; 33]>,
+    pub revoke_authenticator: Option<[u8; 33]>,
+    pub verification_policy: Option<VerificationPolicy>,
+    /// Relying parties set by `UpdateRelyingParties`, `None` falling back to the global set
+    pub relying_parties: Option<Vec<RelyingParty>>,
+    pub nonce: u64,
+    pub expiry: Option<Expiry>,
 }
 
 /// Data for a CPI call (instruction data and account slice)
@@ -44,9 +109,51 @@ pub struct CpiData {
     pub length: u8,      // number of accounts to take from remaining accounts
 }
 
+impl CpiData {
+    /// The accounts of this CPI within the remaining accounts
+    pub fn accounts<'a, 'info>(
+        &self,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<&'a [AccountInfo<'info>]> {
+        let start = self.start_index as usize;
+        remaining_accounts
+            .get(start..start + self.length as usize)
+            .ok_or(LazorKitError::InvalidAccountInput.into())
+    }
+}
+
+/// Program and accounts of one of the wallet's rules, for an instruction lazorkit builds
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RuleAccounts {
+    pub program_index: u8, // index of the rule program in remaining accounts
+    pub start_index: u8,   // starting index in remaining accounts
+    pub length: u8,        // number of accounts to take from remaining accounts
+}
+
+impl RuleAccounts {
+    /// The accounts of this rule within the remaining accounts
+    pub fn accounts<'a, 'info>(
+        &self,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<&'a [AccountInfo<'info>]> {
+        let start = self.start_index as usize;
+        remaining_accounts
+            .get(start..start + self.length as usize)
+            .ok_or(LazorKitError::InvalidAccountInput.into())
+    }
+}
+
+/// One CPI of an `ExecuteBatch` action
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchCpiData {
+    pub program_index: u8, // index of the invoked program in remaining accounts
+    pub rules: Vec<RuleAccounts>, // one per wallet rule, in the wallet's rule order
+    pub cpi_data: CpiData,
+}
+
 /// Entrypoint for executing smart wallet instructions
-pub fn execute_instruction(
-    ctx: Context<ExecuteInstruction>,
+pub fn execute_instruction<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ExecuteInstruction<'info>>,
     args: ExecuteInstructionArgs,
 ) -> Result<()> {
     // --- Account references ---
@@ -61,6 +168,51 @@ pub fn execute_instruction(
         LazorKitError::InvalidPasskey
     );
 
+    // --- An authenticator whose counter once regressed may have been cloned ---
+    require!(
+        !authenticator.clone_suspected,
+        LazorKitError::SignCountRegression
+    );
+
+    // --- Replay protection ---
+    let wallet_config = &mut ctx.accounts.smart_wallet_config;
+    require!(
+        args.nonce == wallet_config.nonce,
+        LazorKitError::InvalidNonce
+    );
+    if let Some(expiry) = args.expiry {
+        require!(
+            !expiry.is_expired(&Clock::get()?),
+            LazorKitError::MessageExpired
+        );
+    }
+    wallet_config.nonce = wallet_config
+        .nonce
+        .checked_add(1)
+        .ok_or(LazorKitError::InvalidNonce)?;
+
+    // --- Rebuild the message the passkey must have signed ---
+    let message = ExecuteMessage::new(
+        &args,
+        ctx.accounts.smart_wallet.key(),
+        ctx.accounts.authenticator_program.key(),
+        ctx.accounts.cpi_program.key(),
+        ctx.remaining_accounts,
+    )?;
+
+    // --- WebAuthn assertion over the message hash ---
+    let challenge = hash(&message.to_bytes()?).to_bytes();
+    let relying_parties = ctx
+        .accounts
+        .smart_wallet_config
+        .allowed_relying_parties(&ctx.accounts.config);
+    let auth_data = webauthn::verify_assertion(
+        &args.authenticator_data,
+        &args.client_data_json,
+        &challenge,
+        relying_parties,
+    )?;
+
     // --- Signature verification using secp256r1 ---
     let secp_ix = load_instruction_at_checked(
         args.verify_instruction_index as usize,
@@ -68,17 +220,51 @@ pub fn execute_instruction(
     )?;
     verify_secp256r1_instruction(
         &secp_ix,
+        &ctx.accounts.ix_sysvar,
         authenticator.passkey_pubkey,
-        args.message,
+        webauthn::signed_message(&args.authenticator_data, &args.client_data_json),
         args.signature,
     )?;
 
+    // --- Signature counter must increase to detect cloned authenticators ---
+    // The regression is recorded rather than failed, a failed transaction dropping both
+    // the flag and the event; the signed action does not run
+    if !authenticator.is_valid_sign_count(auth_data.sign_count) {
+        emit!(SignCountRegression {
+            smart_wallet: ctx.accounts.smart_wallet.key(),
+            smart_wallet_authenticator: authenticator.key(),
+            stored_sign_count: authenticator.sign_count,
+            received_sign_count: auth_data.sign_count,
+        });
+        ctx.accounts.smart_wallet_authenticator.clone_suspected = true;
+        return Ok(());
+    }
+    ctx.accounts.smart_wallet_authenticator.sign_count = auth_data.sign_count;
+
+    // --- User verification policy ---
+    let policy = ctx.accounts.smart_wallet_config.verification_policy;
+    let user_verified = auth_data.has_flags(webauthn::FLAG_USER_VERIFIED);
+    require!(
+        user_verified || !policy.requires_uv(&args.action),
+        LazorKitError::UserNotVerified
+    );
+
+    // --- Keys required in the fixed accounts of rule instructions ---
+    let rule_keys = RuleAccountKeys {
+        payer: ctx.accounts.payer.key(),
+        smart_wallet: ctx.accounts.smart_wallet.key(),
+        smart_wallet_authenticator: ctx.accounts.smart_wallet_authenticator.key(),
+        revoked_authenticator: ctx
+            .accounts
+            .revoked_smart_wallet_authenticator
+            .as_ref()
+            .map(|revoked| revoked.key()),
+    };
+
     // --- Action dispatch ---
     match args.action {
-        Action::ExecuteCpi => {
-            // --- Rule program whitelist check ---
-            let rule_program_key = ctx.accounts.authenticator_program.key();
-            check_whitelist(&ctx.accounts.whitelist_rule_programs, &rule_program_key)?;
+        Action::ExecuteCpi | Action::ExecuteBatch => {
+            let wallet_balance_before = ctx.accounts.smart_wallet.lamports();
 
             // --- Prepare PDA signer for rule CPI ---
             let rule_signer = get_pda_signer(
@@ -86,105 +272,150 @@ pub fn execute_instruction(
                 ctx.accounts.smart_wallet.key(),
                 ctx.bumps.smart_wallet_authenticator,
             );
-            let rule_accounts = &ctx.remaining_accounts[args.rule_data.start_index as usize
-                ..(args.rule_data.start_index as usize + args.rule_data.length as usize)];
 
-            // --- Rule instruction discriminator check ---
-            require!(
-                args.rule_data.data.get(0..8) == Some(&sighash("global", "check_rule")),
-                LazorKitError::InvalidRuleInstruction
-            );
+            // --- Collect (program, wallet rules, instruction) for every CPI ---
+            let calls = if matches!(args.action, Action::ExecuteCpi) {
+                let cpi_data = args
+                    .cpi_data
+                    .as_ref()
+                    .ok_or(LazorKitError::InvalidAccountInput)?;
+                vec![(
+                    ctx.accounts.cpi_program.to_account_info(),
+                    &args.rules,
+                    cpi_data,
+                )]
+            } else {
+                require!(!args.batch.is_empty(), LazorKitError::InvalidAccountInput);
+                args.batch
+                    .iter()
+                    .map(|entry| {
+                        let program = ctx
+                            .remaining_accounts
+                            .get(entry.program_index as usize)
+                            .ok_or(LazorKitError::InvalidAccountInput)?;
+                        Ok((program.clone(), &entry.rules, &entry.cpi_data))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            };
 
-            // --- Execute rule CPI ---
-            execute_cpi(
-                rule_accounts,
-                args.rule_data.data.clone(),
-                &ctx.accounts.authenticator_program,
-                Some(rule_signer),
-            )?;
+            // --- Every CPI must pass all wallet rules before any of them runs ---
+            let mut post_checks = Vec::with_capacity(calls.len());
+            for (program, rules, cpi_data) in &calls {
+                let ix = wallet_cpi_instruction(
+                    program,
+                    cpi_data,
+                    ctx.remaining_accounts,
+                    &ctx.accounts.smart_wallet_config,
+                )?;
+                let rules = resolve_rules(
+                    rules,
+                    &ctx.accounts.smart_wallet_config,
+                    Some(&ctx.accounts.whitelist_rule_programs),
+                    ctx.remaining_accounts,
+                )?;
+                let mut requests = Vec::new();
+                for (rule_program, rule_accounts) in rules {
+                    let request = execute_rule_check(
+                        rule_program,
+                        rule_accounts,
+                        &ix,
+                        &rule_keys,
+                        rule_signer.clone(),
+                    )?;
+                    if let Some(request) = request {
+                        requests.push((rule_program, rule_accounts, request));
+                    }
+                }
+                post_checks.push(requests);
+            }
 
-            // --- CPI for main instruction ---
-            let cpi_data = args
-                .cpi_data
-                .as_ref()
-                .ok_or(LazorKitError::InvalidAccountInput)?;
-            let cpi_accounts = &ctx.remaining_accounts[cpi_data.start_index as usize
-                ..(cpi_data.start_index as usize + cpi_data.length as usize)];
+            // --- Run every CPI, letting rules that asked for it inspect the outcome ---
+            for ((program, _, cpi_data), requests) in calls.iter().zip(post_checks) {
+                let lamports_before = ctx.accounts.smart_wallet.lamports();
+                let token_amounts_before = requests
+                    .iter()
+                    .map(|(_, _, request)| {
+                        token_amounts(&request.token_accounts, ctx.remaining_accounts)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
 
-            // --- Special handling for SOL transfer ---
-            if cpi_data.data.get(0..4) == Some(&SOL_TRANSFER_DISCRIMINATOR)
-                && ctx.accounts.cpi_program.key() == anchor_lang::solana_program::system_program::ID
-            {
-                require!(
-                    ctx.remaining_accounts.len() >= 2,
-                    LazorKitError::InvalidAccountInput
-                );
-                let amount = u64::from_le_bytes(cpi_data.data[4..12].try_into().unwrap());
-                transfer_sol_from_pda(
+                execute_wallet_cpi(
+                    program,
+                    cpi_data,
+                    ctx.remaining_accounts,
                     &ctx.accounts.smart_wallet,
-                    &ctx.remaining_accounts[1].to_account_info(),
-                    amount,
-                )?;
-            } else {
-                // --- Generic CPI with wallet signer ---
-                let wallet = &ctx.accounts.smart_wallet_config;
-                let wallet_signer = PdaSigner {
-                    seeds: [SMART_WALLET_SEED, wallet.id.to_le_bytes().as_ref()].concat(),
-                    bump: wallet.bump,
-                };
-                execute_cpi(
-                    cpi_accounts,
-                    cpi_data.data.clone(),
-                    &ctx.accounts.cpi_program,
-                    Some(wallet_signer),
+                    &ctx.accounts.smart_wallet_config,
                 )?;
+
+                for ((rule_program, rule_accounts, request), before) in
+                    requests.iter().zip(token_amounts_before)
+                {
+                    let after = token_amounts(&request.token_accounts, ctx.remaining_accounts)?;
+                    let post_check_args = PostCheckArgs {
+                        lamports: BalanceChange {
+                            before: lamports_before,
+                            after: ctx.accounts.smart_wallet.lamports(),
+                        },
+                        token_accounts: request
+                            .token_accounts
+                            .iter()
+                            .zip(before.into_iter().zip(after))
+                            .map(|(account, (before, after))| TokenAccountChange {
+                                account: *account,
+                                amount: BalanceChange { before, after },
+                            })
+                            .collect(),
+                    };
+                    execute_post_check(
+                        rule_program,
+                        rule_accounts,
+                        &post_check_args,
+                        &rule_keys,
+                        rule_signer.clone(),
+                    )?;
+                }
             }
+
+            // --- Anything but small SOL transfers needs user verification ---
+            let outflow =
+                wallet_balance_before.saturating_sub(ctx.accounts.smart_wallet.lamports());
+            let sol_transfers_only = calls
+                .iter()
+                .all(|(program, _, cpi_data)| is_sol_transfer(program, cpi_data));
+            require!(
+                user_verified || !policy.requires_uv_for_execute(outflow, sol_transfers_only),
+                LazorKitError::UserNotVerified
+            );
         }
         Action::ChangeProgramRule => {
             // --- Change rule program logic ---
             let old_rule_program_key = ctx.accounts.authenticator_program.key();
             let new_rule_program_key = ctx.accounts.cpi_program.key();
-            let whitelist = &ctx.accounts.whitelist_rule_programs;
             let wallet_config = &mut ctx.accounts.smart_wallet_config;
             let cpi_data = args
                 .cpi_data
                 .as_ref()
                 .ok_or(LazorKitError::InvalidAccountInput)?;
+            check_whitelist(&ctx.accounts.whitelist_rule_programs, &new_rule_program_key)?;
 
-            check_whitelist(whitelist, &old_rule_program_key)?;
-            check_whitelist(whitelist, &new_rule_program_key)?;
-
-            // --- Destroy/init discriminators check ---
-            require!(
-                args.rule_data.data.get(0..8) == Some(&sighash("global", "destroy")),
-                LazorKitError::InvalidRuleInstruction
-            );
-            require!(
-                cpi_data.data.get(0..8) == Some(&sighash("global", "init_rule")),
-                LazorKitError::InvalidRuleInstruction
-            );
-
-            // --- Only one of the programs can be the default, and they must differ ---
-            let default_rule_program = ctx.accounts.config.default_rule_program;
-            require!(
-                (old_rule_program_key == default_rule_program
-                    || new_rule_program_key == default_rule_program)
-                    && (old_rule_program_key != new_rule_program_key),
-                LazorKitError::InvalidRuleProgram
-            );
+            // --- Destroy/init must follow the rule interface ---
+            rule_keys.check(
+                RuleInstruction::Destroy,
+                &args.rule_data,
+                ctx.remaining_accounts,
+            )?;
+            rule_keys.check(RuleInstruction::InitRule, cpi_data, ctx.remaining_accounts)?;
 
-            // --- Update rule program in config ---
-            wallet_config.rule_program = new_rule_program_key;
+            // --- Any whitelisted rule can take over, the old rule authorizing its destroy ---
+            wallet_config.replace_rule_program(&old_rule_program_key, new_rule_program_key)?;
 
-            // --- Destroy old rule program ---
+            // --- Destroy old rule program, even once de-listed, so it can clean up ---
             let rule_signer = get_pda_signer(
                 &args.passkey_pubkey,
                 ctx.accounts.smart_wallet.key(),
                 ctx.bumps.smart_wallet_authenticator,
             );
-            let rule_accounts = &ctx.remaining_accounts[args.rule_data.start_index as usize
-                ..(args.rule_data.start_index as usize + args.rule_data.length as usize)];
-
+            let rule_accounts = args.rule_data.accounts(ctx.remaining_accounts)?;
             execute_cpi(
                 rule_accounts,
                 args.rule_data.data.clone(),
@@ -193,8 +424,7 @@ pub fn execute_instruction(
             )?;
 
             // --- Init new rule program ---
-            let cpi_accounts = &ctx.remaining_accounts[cpi_data.start_index as usize
-                ..(cpi_data.start_index as usize + cpi_data.length as usize)];
+            let cpi_accounts = cpi_data.accounts(ctx.remaining_accounts)?;
             execute_cpi(
                 cpi_accounts,
                 cpi_data.data.clone(),
@@ -207,18 +437,35 @@ pub fn execute_instruction(
             let rule_program_key = ctx.accounts.authenticator_program.key();
             check_whitelist(&ctx.accounts.whitelist_rule_programs, &rule_program_key)?;
 
+            // --- Interface instructions only run through the actions built for them ---
+            require!(
+                RuleInstruction::parse(&args.rule_data.data).is_none(),
+                LazorKitError::InvalidRuleInstruction
+            );
+
             // --- Optionally create a new smart wallet authenticator ---
             if let Some(new_authenticator) = args.create_new_authenticator {
                 let new_auth = ctx
                     .accounts
                     .new_smart_wallet_authenticator
                     .as_mut()
-                    .unwrap();
+                    .ok_or(LazorKitError::InvalidAccountInput)?;
                 new_auth.smart_wallet = ctx.accounts.smart_wallet.key();
                 new_auth.passkey_pubkey = new_authenticator;
+                new_auth.sign_count = 0;
+                new_auth.clone_suspected = false;
                 new_auth.bump = ctx.bumps.new_smart_wallet_authenticator.unwrap_or_default();
+
+                let wallet_config = &mut ctx.accounts.smart_wallet_config;
+                wallet_config.authenticator_count = wallet_config
+                    .authenticator_count
+                    .checked_add(1)
+                    .ok_or(LazorKitError::InvalidAccountInput)?;
             } else {
-                return Err(LazorKitError::InvalidAccountInput.into());
+                require!(
+                    ctx.accounts.new_smart_wallet_authenticator.is_none(),
+                    LazorKitError::InvalidAccountInput
+                );
             }
 
             let rule_signer = get_pda_signer(
@@ -226,8 +473,7 @@ pub fn execute_instruction(
                 ctx.accounts.smart_wallet.key(),
                 ctx.bumps.smart_wallet_authenticator,
             );
-            let rule_accounts = &ctx.remaining_accounts[args.rule_data.start_index as usize
-                ..(args.rule_data.start_index as usize + args.rule_data.length as usize)];
+            let rule_accounts = args.rule_data.accounts(ctx.remaining_accounts)?;
             execute_cpi(
                 rule_accounts,
                 args.rule_data.data.clone(),
@@ -235,9 +481,223 @@ pub fn execute_instruction(
                 Some(rule_signer),
             )?;
         }
+        Action::RevokeAuthenticator => {
+            let revoked = ctx
+                .accounts
+                .revoked_smart_wallet_authenticator
+                .as_ref()
+                .ok_or(LazorKitError::InvalidAccountInput)?;
+            require!(
+                revoked.key() != ctx.accounts.smart_wallet_authenticator.key(),
+                LazorKitError::InvalidAuthenticator
+            );
+
+            // --- A wallet must keep at least one authenticator ---
+            let wallet_config = &mut ctx.accounts.smart_wallet_config;
+            require!(
+                wallet_config.authenticator_count > 1,
+                LazorKitError::LastAuthenticator
+            );
+            wallet_config.authenticator_count -= 1;
+
+            // --- Let every rule program clean up its state for the revoked authenticator ---
+            let rule_signer = get_pda_signer(
+                &args.passkey_pubkey,
+                ctx.accounts.smart_wallet.key(),
+                ctx.bumps.smart_wallet_authenticator,
+            );
+            let rules = resolve_rules(
+                &args.rules,
+                &ctx.accounts.smart_wallet_config,
+                None,
+                ctx.remaining_accounts,
+            )?;
+            for (rule_program, rule_accounts) in rules {
+                require!(
+                    rule_keys.matches(RuleInstruction::RevokeAuthenticator, rule_accounts),
+                    LazorKitError::InvalidRuleInstruction
+                );
+                execute_cpi(
+                    rule_accounts,
+                    RuleInstruction::RevokeAuthenticator.data(&()),
+                    rule_program,
+                    Some(rule_signer.clone()),
+                )?;
+            }
+
+            // --- Close the authenticator, refunding its rent to the smart wallet ---
+            revoked.close(ctx.accounts.smart_wallet.to_account_info())?;
+        }
+        Action::AddRuleProgram => {
+            let new_rule_program_key = ctx.accounts.cpi_program.key();
+            let cpi_data = args
+                .cpi_data
+                .as_ref()
+                .ok_or(LazorKitError::InvalidAccountInput)?;
+            check_whitelist(&ctx.accounts.whitelist_rule_programs, &new_rule_program_key)?;
+            rule_keys.check(RuleInstruction::InitRule, cpi_data, ctx.remaining_accounts)?;
+
+            // --- Every wallet rule must approve the change, as it would a CPI ---
+            check_wallet_update(
+                &wallet_update_instruction(
+                    &args.action,
+                    ctx.accounts.smart_wallet_config.key(),
+                    &new_rule_program_key,
+                )?,
+                &args.rules,
+                &ctx.accounts.smart_wallet_config,
+                Some(&ctx.accounts.whitelist_rule_programs),
+                ctx.remaining_accounts,
+                &rule_keys,
+                get_pda_signer(
+                    &args.passkey_pubkey,
+                    ctx.accounts.smart_wallet.key(),
+                    ctx.bumps.smart_wallet_authenticator,
+                ),
+            )?;
+
+            // --- New rules run after the existing ones ---
+            let wallet_config = &mut ctx.accounts.smart_wallet_config;
+            require!(
+                !wallet_config.has_rule_program(&new_rule_program_key),
+                LazorKitError::RuleProgramAlreadyAttached
+            );
+            require!(
+                wallet_config.rule_programs.len() < SmartWalletConfig::MAX_RULE_PROGRAMS,
+                LazorKitError::TooManyRulePrograms
+            );
+            wallet_config.rule_programs.push(new_rule_program_key);
+
+            // --- Init new rule program ---
+            let rule_signer = get_pda_signer(
+                &args.passkey_pubkey,
+                ctx.accounts.smart_wallet.key(),
+                ctx.bumps.smart_wallet_authenticator,
+            );
+            execute_cpi(
+                cpi_data.accounts(ctx.remaining_accounts)?,
+                cpi_data.data.clone(),
+                &ctx.accounts.cpi_program,
+                Some(rule_signer),
+            )?;
+        }
+        Action::RemoveRuleProgram => {
+            let rule_program_key = ctx.accounts.authenticator_program.key();
+
+            // --- Every wallet rule must approve the change, even one that was de-listed ---
+            check_wallet_update(
+                &wallet_update_instruction(
+                    &args.action,
+                    ctx.accounts.smart_wallet_config.key(),
+                    &rule_program_key,
+                )?,
+                &args.rules,
+                &ctx.accounts.smart_wallet_config,
+                None,
+                ctx.remaining_accounts,
+                &rule_keys,
+                get_pda_signer(
+                    &args.passkey_pubkey,
+                    ctx.accounts.smart_wallet.key(),
+                    ctx.bumps.smart_wallet_authenticator,
+                ),
+            )?;
+
+            // --- A wallet must keep at least one rule ---
+            let wallet_config = &mut ctx.accounts.smart_wallet_config;
+            require!(
+                wallet_config.rule_programs.len() > 1,
+                LazorKitError::LastRuleProgram
+            );
+            wallet_config
+                .rule_programs
+                .retain(|program| *program != rule_program_key);
+
+            // --- The rule is destroyed even once de-listed, so it can clean up ---
+            rule_keys.check(
+                RuleInstruction::Destroy,
+                &args.rule_data,
+                ctx.remaining_accounts,
+            )?;
+            let rule_signer = get_pda_signer(
+                &args.passkey_pubkey,
+                ctx.accounts.smart_wallet.key(),
+                ctx.bumps.smart_wallet_authenticator,
+            );
+            execute_cpi(
+                args.rule_data.accounts(ctx.remaining_accounts)?,
+                args.rule_data.data.clone(),
+                &ctx.accounts.authenticator_program,
+                Some(rule_signer),
+            )?;
+        }
         Action::CheckAuthenticator => {
             // --- No-op: used for checking authenticator existence ---
         }
+        Action::UpdateVerificationPolicy => {
+            let verification_policy = args
+                .verification_policy
+                .ok_or(LazorKitError::InvalidAccountInput)?;
+
+            // --- Every wallet rule must approve the change, as it would a CPI ---
+            check_wallet_update(
+                &wallet_update_instruction(
+                    &args.action,
+                    ctx.accounts.smart_wallet_config.key(),
+                    &verification_policy,
+                )?,
+                &args.rules,
+                &ctx.accounts.smart_wallet_config,
+                Some(&ctx.accounts.whitelist_rule_programs),
+                ctx.remaining_accounts,
+                &rule_keys,
+                get_pda_signer(
+                    &args.passkey_pubkey,
+                    ctx.accounts.smart_wallet.key(),
+                    ctx.bumps.smart_wallet_authenticator,
+                ),
+            )?;
+
+            ctx.accounts.smart_wallet_config.verification_policy = verification_policy;
+        }
+        Action::UpdateRelyingParties => {
+            if let Some(relying_parties) = &args.relying_parties {
+                RelyingParty::validate_set(
+                    relying_parties,
+                    SmartWalletConfig::MAX_RELYING_PARTIES,
+                )?;
+            }
+
+            // --- Every wallet rule must approve the change, as it would a CPI ---
+            check_wallet_update(
+                &wallet_update_instruction(
+                    &args.action,
+                    ctx.accounts.smart_wallet_config.key(),
+                    &args.relying_parties,
+                )?,
+                &args.rules,
+                &ctx.accounts.smart_wallet_config,
+                Some(&ctx.accounts.whitelist_rule_programs),
+                ctx.remaining_accounts,
+                &rule_keys,
+                get_pda_signer(
+                    &args.passkey_pubkey,
+                    ctx.accounts.smart_wallet.key(),
+                    ctx.bumps.smart_wallet_authenticator,
+                ),
+            )?;
+
+            ctx.accounts.smart_wallet_config.relying_parties = args.relying_parties.clone();
+        }
+    }
+
+    // --- Protocol execution fee ---
+    if ctx.accounts.config.execute_fee > 0 {
+        transfer_sol_from_pda(
+            &ctx.accounts.smart_wallet,
+            &ctx.accounts.treasury,
+            ctx.accounts.config.execute_fee,
+        )?;
     }
 
     // --- Reimburse payer if balance changed ---
@@ -254,6 +714,247 @@ pub fn execute_instruction(
     Ok(())
 }
 
+/// Run the rule program's `check_rule` for an instruction the wallet is about to execute,
+/// returning its request for a `post_check`, if any.
+///
+/// The payload is built from the instruction itself, so the rule always judges the
+/// operation that really runs; the client only supplies the rule's accounts.
+fn execute_rule_check(
+    rule_program: &AccountInfo,
+    rule_accounts: &[AccountInfo],
+    ix: &Instruction,
+    rule_keys: &RuleAccountKeys,
+    rule_signer: PdaSigner,
+) -> Result<Option<PostCheckRequest>> {
+    require!(
+        rule_keys.matches(RuleInstruction::CheckRule, rule_accounts),
+        LazorKitError::InvalidRuleInstruction
+    );
+    execute_cpi(
+        rule_accounts,
+        CheckRuleArgs::from(ix).to_instruction_data(),
+        rule_program,
+        Some(rule_signer),
+    )?;
+
+    // --- Return data is cleared on every CPI, so any left is the rule's own ---
+    match get_return_data() {
+        Some((program_id, data)) if program_id == rule_program.key() => {
+            let request = PostCheckRequest::try_from_slice(&data)
+                .map_err(|_| LazorKitError::InvalidRuleInstruction)?;
+            Ok(Some(request))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Run the rule program's `post_check` once an instruction it approved has run
+fn execute_post_check(
+    rule_program: &AccountInfo,
+    rule_accounts: &[AccountInfo],
+    args: &PostCheckArgs,
+    rule_keys: &RuleAccountKeys,
+    rule_signer: PdaSigner,
+) -> Result<()> {
+    require!(
+        rule_keys.matches(RuleInstruction::PostCheck, rule_accounts),
+        LazorKitError::InvalidRuleInstruction
+    );
+    execute_cpi(
+        rule_accounts,
+        args.to_instruction_data(),
+        rule_program,
+        Some(rule_signer),
+    )
+}
+
+/// Amounts of the given token accounts, which must be among the remaining accounts
+fn token_amounts(keys: &[Pubkey], remaining_accounts: &[AccountInfo]) -> Result<Vec<u64>> {
+    keys.iter()
+        .map(|key| {
+            let account = remaining_accounts
+                .iter()
+                .find(|account| account.key == key)
+                .ok_or(LazorKitError::InvalidAccountInput)?;
+            token_account_amount(account)
+        })
+        .collect()
+}
+
+/// Resolve the program and accounts of every wallet rule, which must be given in the
+/// wallet's rule order. With a `whitelist`, every rule must still be listed; paths that
+/// only let rules clean up, such as revoking an authenticator, pass `None` so a de-listed
+/// rule cannot block them
+fn resolve_rules<'a, 'info>(
+    rules: &[RuleAccounts],
+    wallet_config: &SmartWalletConfig,
+    whitelist: Option<&WhitelistRulePrograms>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<Vec<(&'a AccountInfo<'info>, &'a [AccountInfo<'info>])>> {
+    require!(
+        rules.len() == wallet_config.rule_programs.len(),
+        LazorKitError::InvalidAccountInput
+    );
+    rules
+        .iter()
+        .zip(&wallet_config.rule_programs)
+        .map(|(rule, rule_program_key)| {
+            if let Some(whitelist) = whitelist {
+                check_whitelist(whitelist, rule_program_key)?;
+            }
+            let rule_program = remaining_accounts
+                .get(rule.program_index as usize)
+                .ok_or(LazorKitError::InvalidAccountInput)?;
+            require!(
+                rule_program.key() == *rule_program_key,
+                LazorKitError::InvalidRuleProgram
+            );
+            Ok((rule_program, rule.accounts(remaining_accounts)?))
+        })
+        .collect()
+}
+
+/// Have every wallet rule approve a change to the wallet itself through `check_rule`, the
+/// change being described by `wallet_update_instruction`
+fn check_wallet_update<'info>(
+    ix: &Instruction,
+    rules: &[RuleAccounts],
+    wallet_config: &Account<'info, SmartWalletConfig>,
+    whitelist: Option<&WhitelistRulePrograms>,
+    remaining_accounts: &[AccountInfo<'info>],
+    rule_keys: &RuleAccountKeys,
+    rule_signer: PdaSigner,
+) -> Result<()> {
+    let rules = resolve_rules(rules, wallet_config, whitelist, remaining_accounts)?;
+    for (rule_program, rule_accounts) in rules {
+        execute_rule_check(
+            rule_program,
+            rule_accounts,
+            ix,
+            rule_keys,
+            rule_signer.clone(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Keys lazorkit requires in the fixed accounts of rule instructions
+struct RuleAccountKeys {
+    payer: Pubkey,
+    smart_wallet: Pubkey,
+    smart_wallet_authenticator: Pubkey,
+    revoked_authenticator: Option<Pubkey>,
+}
+
+impl RuleAccountKeys {
+    fn get(&self, role: RuleAccount) -> Option<Pubkey> {
+        match role {
+            RuleAccount::Payer => Some(self.payer),
+            RuleAccount::SmartWallet => Some(self.smart_wallet),
+            RuleAccount::SmartWalletAuthenticator => Some(self.smart_wallet_authenticator),
+            RuleAccount::RevokedAuthenticator => self.revoked_authenticator,
+        }
+    }
+
+    /// Whether `accounts` start with the fixed accounts of `ix`
+    fn matches(&self, ix: RuleInstruction, accounts: &[AccountInfo]) -> bool {
+        let keys = accounts.iter().map(|acc| acc.key()).collect::<Vec<_>>();
+        ix.matches_layout(&keys, |role| self.get(role))
+    }
+
+    /// Check that a client-supplied rule CPI is `ix` with the required fixed accounts
+    fn check(
+        &self,
+        ix: RuleInstruction,
+        cpi_data: &CpiData,
+        remaining_accounts: &[AccountInfo],
+    ) -> Result<()> {
+        require!(
+            RuleInstruction::parse(&cpi_data.data) == Some(ix)
+                && self.matches(ix, cpi_data.accounts(remaining_accounts)?),
+            LazorKitError::InvalidRuleInstruction
+        );
+        Ok(())
+    }
+}
+
+/// Instruction the wallet executes for `cpi_data`, with the wallet PDA as signer
+fn wallet_cpi_instruction(
+    program: &AccountInfo,
+    cpi_data: &CpiData,
+    remaining_accounts: &[AccountInfo],
+    wallet_config: &SmartWalletConfig,
+) -> Result<Instruction> {
+    Ok(create_cpi_instruction(
+        cpi_data.accounts(remaining_accounts)?,
+        cpi_data.data.clone(),
+        program,
+        &Some(wallet_signer(wallet_config)),
+    ))
+}
+
+/// Instruction presented to the wallet rules' `check_rule` for an action updating the
+/// wallet configuration: lazorkit, writing the wallet config, with the action and its
+/// new value as data
+fn wallet_update_instruction(
+    action: &Action,
+    smart_wallet_config: Pubkey,
+    value: &impl AnchorSerialize,
+) -> Result<Instruction> {
+    let mut data = action.try_to_vec()?;
+    value.serialize(&mut data)?;
+    Ok(Instruction {
+        program_id: ID,
+        accounts: vec![AccountMeta::new(smart_wallet_config, false)],
+        data,
+    })
+}
+
+/// Whether `cpi_data` is a system program SOL transfer
+fn is_sol_transfer(program: &AccountInfo, cpi_data: &CpiData) -> bool {
+    cpi_data.data.get(0..4) == Some(&SOL_TRANSFER_DISCRIMINATOR)
+        && program.key() == anchor_lang::solana_program::system_program::ID
+}
+
+/// Signer seeds of the smart wallet PDA
+fn wallet_signer(wallet_config: &SmartWalletConfig) -> PdaSigner {
+    PdaSigner {
+        seeds: [SMART_WALLET_SEED, wallet_config.id.to_le_bytes().as_ref()].concat(),
+        bump: wallet_config.bump,
+    }
+}
+
+/// Execute a CPI on behalf of the smart wallet
+fn execute_wallet_cpi(
+    program: &AccountInfo,
+    cpi_data: &CpiData,
+    remaining_accounts: &[AccountInfo],
+    smart_wallet: &AccountInfo,
+    wallet_config: &SmartWalletConfig,
+) -> Result<()> {
+    let cpi_accounts = cpi_data.accounts(remaining_accounts)?;
+
+    // --- Special handling for SOL transfer, the wallet PDA is not system-owned ---
+    if is_sol_transfer(program, cpi_data) {
+        require!(
+            cpi_accounts.len() >= 2
+                && cpi_accounts[0].key() == smart_wallet.key()
+                && cpi_data.data.len() >= 12,
+            LazorKitError::InvalidAccountInput
+        );
+        let amount = u64::from_le_bytes(cpi_data.data[4..12].try_into().unwrap());
+        return transfer_sol_from_pda(smart_wallet, &cpi_accounts[1], amount);
+    }
+
+    // --- Generic CPI with wallet signer ---
+    execute_cpi(
+        cpi_accounts,
+        cpi_data.data.clone(),
+        program,
+        Some(wallet_signer(wallet_config)),
+    )
+}
+
 /// Accounts context for execute_instruction
 #[derive(Accounts)]
 #[instruction(args: ExecuteInstructionArgs)]
@@ -268,6 +969,13 @@ pub struct ExecuteInstruction<'info> {
     )]
     pub config: Box<Account<'info, Config>>,
 
+    #[account(
+        mut,
+        address = config.treasury,
+    )]
+    /// CHECK: Receives the execution fee.
+    pub treasury: UncheckedAccount<'info>,
+
     #[account(
         mut,
         seeds = [SMART_WALLET_SEED, smart_wallet_config.id.to_le_bytes().as_ref()],
@@ -286,6 +994,7 @@ pub struct ExecuteInstruction<'info> {
     pub smart_wallet_config: Box<Account<'info, SmartWalletConfig>>,
 
     #[account(
+        mut,
         seeds = [args.passkey_pubkey.to_hashed_bytes(smart_wallet.key()).as_ref()],
         bump,
         owner = ID,
@@ -299,7 +1008,11 @@ pub struct ExecuteInstruction<'info> {
     )]
     pub whitelist_rule_programs: Box<Account<'info, WhitelistRulePrograms>>,
 
-    /// CHECK: Used for rule CPI.
+    #[account(
+        constraint = smart_wallet_config.has_rule_program(authenticator_program.key)
+            @ LazorKitError::InvalidRuleProgram
+    )]
+    /// CHECK: One of the wallet's own rule programs, targeted by rule actions.
     pub authenticator_program: UncheckedAccount<'info>,
 
     #[account(address = IX_ID)]
@@ -319,4 +1032,12 @@ pub struct ExecuteInstruction<'info> {
         bump,
     )]
     pub new_smart_wallet_authenticator: Option<Account<'info, SmartWalletAuthenticator>>,
+
+    #[account(
+        mut,
+        seeds = [args.revoke_authenticator.unwrap_or([0; 33]).to_hashed_bytes(smart_wallet.key()).as_ref()],
+        bump,
+        owner = ID,
+    )]
+    pub revoked_smart_wallet_authenticator: Option<Account<'info, SmartWalletAuthenticator>>,
 }
@@ -2,22 +2,86 @@ use anchor_lang::prelude::*;
 
 use crate::{
     constants::AUTHORITY_SEED,
-    state::{Config, SmartWalletSeq, WhitelistRulePrograms},
+    error::LazorKitError,
+    program::Lazorkit,
+    state::{Config, RelyingParty, SmartWalletSeq, WhitelistRulePrograms},
+    utils::create_pda_account,
+    ID,
 };
 
-pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-    let whitelist_rule_programs = &mut ctx.accounts.whitelist_rule_programs;
-    whitelist_rule_programs.list = vec![ctx.accounts.default_rule_program.key()];
+pub fn initialize(ctx: Context<Initialize>, relying_parties: Vec<RelyingParty>) -> Result<()> {
+    // The accounts are created here rather than with `init`, which would fail a second call
+    // with the system program's "already in use" error before this check could run
+    require!(
+        ctx.accounts.config.data_is_empty(),
+        LazorKitError::AlreadyInitialized
+    );
+    RelyingParty::validate_set(&relying_parties, Config::MAX_RELYING_PARTIES)?;
 
-    let smart_wallet_seq = &mut ctx.accounts.smart_wallet_seq;
-    smart_wallet_seq.seq = 0;
+    let accounts = &ctx.accounts;
+    let bumps = &ctx.bumps;
+    let payer = accounts.signer.to_account_info();
+    let system_program = accounts.system_program.to_account_info();
 
-    let config = &mut ctx.accounts.config;
-    config.create_smart_wallet_fee = 0; // LAMPORTS
-    config.default_rule_program = ctx.accounts.default_rule_program.key();
-    config.authority_bump = ctx.bumps.authority;
+    create_pda_account(
+        &payer,
+        &accounts.config,
+        &system_program,
+        8 + Config::INIT_SPACE,
+        &ID,
+        &[Config::PREFIX_SEED, &[bumps.config]],
+    )?;
+    Config {
+        admin: accounts.signer.key(),
+        pending_admin: None,
+        create_smart_wallet_fee: 0, // LAMPORTS
+        execute_fee: 0,             // LAMPORTS
+        treasury: accounts.signer.key(),
+        default_rule_program: accounts.default_rule_program.key(),
+        authority_bump: bumps.authority,
+        relying_parties,
+    }
+    .try_serialize(&mut &mut accounts.config.try_borrow_mut_data()?[..])?;
 
-    Ok(())
+    create_pda_account(
+        &payer,
+        &accounts.whitelist_rule_programs,
+        &system_program,
+        WhitelistRulePrograms::space(1),
+        &ID,
+        &[
+            WhitelistRulePrograms::PREFIX_SEED,
+            &[bumps.whitelist_rule_programs],
+        ],
+    )?;
+    WhitelistRulePrograms {
+        list: vec![accounts.default_rule_program.key()],
+        bump: bumps.whitelist_rule_programs,
+    }
+    .try_serialize(&mut &mut accounts.whitelist_rule_programs.try_borrow_mut_data()?[..])?;
+
+    create_pda_account(
+        &payer,
+        &accounts.smart_wallet_seq,
+        &system_program,
+        8 + SmartWalletSeq::INIT_SPACE,
+        &ID,
+        &[SmartWalletSeq::PREFIX_SEED, &[bumps.smart_wallet_seq]],
+    )?;
+    SmartWalletSeq {
+        seq: 0,
+        bump: bumps.smart_wallet_seq,
+    }
+    .try_serialize(&mut &mut accounts.smart_wallet_seq.try_borrow_mut_data()?[..])?;
+
+    create_pda_account(
+        &payer,
+        &accounts.authority,
+        &system_program,
+        0,
+        &ID,
+        &[AUTHORITY_SEED, &[bumps.authority]],
+    )
 }
 
 #[derive(Accounts)]
@@ -26,36 +90,31 @@ pub struct Initialize<'info> {
     pub signer: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = signer,
-        space = 8 + Config::INIT_SPACE,
+        mut,
         seeds = [Config::PREFIX_SEED],
         bump,
     )]
-    pub config: Box<Account<'info, Config>>,
+    /// CHECK: Created by `initialize`, which fails if it already exists.
+    pub config: UncheckedAccount<'info>,
 
     #[account(
-        init_if_needed,
-        payer = signer,
-        space = 8 + WhitelistRulePrograms::INIT_SPACE,
+        mut,
         seeds = [WhitelistRulePrograms::PREFIX_SEED],
         bump
     )]
-    pub whitelist_rule_programs: Box<Account<'info, WhitelistRulePrograms>>,
+    /// CHECK: Created by `initialize`.
+    pub whitelist_rule_programs: UncheckedAccount<'info>,
 
     #[account(
-        init_if_needed,
-        payer = signer,
-        space = 8 + SmartWalletSeq::INIT_SPACE,
+        mut,
         seeds = [SmartWalletSeq::PREFIX_SEED],
         bump
     )]
-    pub smart_wallet_seq: Box<Account<'info, SmartWalletSeq>>,
+    /// CHECK: Created by `initialize`.
+    pub smart_wallet_seq: UncheckedAccount<'info>,
 
     #[account(
-        init_if_needed,
-        payer = signer,
-        space = 0,
+        mut,
         seeds = [AUTHORITY_SEED],
         bump,
     )]
@@ -65,5 +124,14 @@ pub struct Initialize<'info> {
     /// CHECK:
     pub default_rule_program: UncheckedAccount<'info>,
 
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Lazorkit>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key())
+            @ LazorKitError::InvalidUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
     pub system_program: Program<'info, System>,
 }
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::LazorKitError,
+    state::{LegacySmartWalletAuthenticator, SmartWalletAuthenticator, SmartWalletConfig},
+    utils::write_account,
+    ID,
+};
+
+/// Move a legacy authenticator left out of `migrate_smart_wallet` to the current layout,
+/// counting it among the wallet's authenticators
+pub fn migrate_authenticator(ctx: Context<MigrateAuthenticator>) -> Result<()> {
+    let account = ctx.accounts.smart_wallet_authenticator.to_account_info();
+    let authenticator = LegacySmartWalletAuthenticator::parse(&account.try_borrow_data()?)
+        .ok_or(LazorKitError::AccountAlreadyMigrated)?;
+    require!(
+        authenticator.smart_wallet == ctx.accounts.smart_wallet.key(),
+        LazorKitError::InvalidAccountInput
+    );
+    write_account(
+        &account,
+        &authenticator.migrate(),
+        8 + SmartWalletAuthenticator::INIT_SPACE,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    let wallet_config = &mut ctx.accounts.smart_wallet_config;
+    wallet_config.authenticator_count = wallet_config
+        .authenticator_count
+        .checked_add(1)
+        .ok_or(LazorKitError::InvalidAccountInput)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateAuthenticator<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: This account is only used for its public key and seeds.
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    /// Migrated by `migrate_smart_wallet` first
+    #[account(
+        mut,
+        seeds = [SmartWalletConfig::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump,
+    )]
+    pub smart_wallet_config: Account<'info, SmartWalletConfig>,
+
+    #[account(mut, owner = ID)]
+    /// CHECK: Holds a legacy authenticator, which does not deserialize as `SmartWalletAuthenticator`.
+    pub smart_wallet_authenticator: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
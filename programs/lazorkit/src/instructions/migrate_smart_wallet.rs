@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::LazorKitError,
+    state::{
+        LegacySmartWalletAuthenticator, LegacySmartWalletConfig, SmartWalletAuthenticator,
+        SmartWalletConfig,
+    },
+    utils::write_account,
+    ID,
+};
+
+/// Move a smart wallet created before its config was versioned to the current account
+/// layouts. The wallet's legacy authenticators are passed as writable remaining accounts
+/// and migrated along with it; the ones passed make up the wallet's authenticator count,
+/// any left out being counted as `migrate_authenticator` moves them later.
+pub fn migrate_smart_wallet<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MigrateSmartWallet<'info>>,
+) -> Result<()> {
+    let payer = ctx.accounts.payer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    let config_info = ctx.accounts.smart_wallet_config.to_account_info();
+    let legacy = LegacySmartWalletConfig::parse(&config_info.try_borrow_data()?)
+        .ok_or(LazorKitError::AccountAlreadyMigrated)?;
+
+    let mut authenticators: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account in ctx.remaining_accounts {
+        require!(
+            account.owner == &ID && account.is_writable && !authenticators.contains(account.key),
+            LazorKitError::InvalidAccountInput
+        );
+        let authenticator = LegacySmartWalletAuthenticator::parse(&account.try_borrow_data()?)
+            .ok_or(LazorKitError::InvalidAccountInput)?;
+        require!(
+            authenticator.smart_wallet == ctx.accounts.smart_wallet.key(),
+            LazorKitError::InvalidAccountInput
+        );
+        write_account(
+            account,
+            &authenticator.migrate(),
+            8 + SmartWalletAuthenticator::INIT_SPACE,
+            &payer,
+            &system_program,
+        )?;
+        authenticators.push(*account.key);
+    }
+    require!(
+        !authenticators.is_empty(),
+        LazorKitError::InvalidAccountInput
+    );
+
+    write_account(
+        &config_info,
+        &legacy.migrate(authenticators.len() as u16),
+        8 + SmartWalletConfig::INIT_SPACE,
+        &payer,
+        &system_program,
+    )
+}
+
+#[derive(Accounts)]
+pub struct MigrateSmartWallet<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: This account is only used for its public key and seeds.
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SmartWalletConfig::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump,
+        owner = ID,
+    )]
+    /// CHECK: Holds a legacy config, which does not deserialize as `SmartWalletConfig`.
+    pub smart_wallet_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
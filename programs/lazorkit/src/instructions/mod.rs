@@ -1,9 +1,21 @@
+mod accept_admin;
 mod create_smart_wallet;
 mod execute_instruction;
 mod initialize;
+mod migrate_authenticator;
+mod migrate_smart_wallet;
+mod propose_admin;
+mod remove_whitelist_rule_program;
+mod update_config;
 mod upsert_whitelist_rule_programs;
 
+pub use accept_admin::*;
 pub use create_smart_wallet::*;
 pub use execute_instruction::*;
 pub use initialize::*;
+pub use migrate_authenticator::*;
+pub use migrate_smart_wallet::*;
+pub use propose_admin::*;
+pub use remove_whitelist_rule_program::*;
+pub use update_config::*;
 pub use upsert_whitelist_rule_programs::*;
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::LazorKitError, state::Config, ID};
+
+/// First step of an admin transfer: record the proposed admin, who must then accept
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.config.pending_admin = Some(new_admin);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Config::PREFIX_SEED],
+        bump,
+        owner = ID,
+        has_one = admin @ LazorKitError::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
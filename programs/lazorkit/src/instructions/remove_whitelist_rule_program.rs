@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::LazorKitError,
+    state::{Config, WhitelistRulePrograms},
+    utils::resize_account,
+    ID,
+};
+
+/// Remove a program, and any duplicate entries of it, from the whitelist
+pub fn remove_whitelist_rule_program(
+    ctx: Context<RemoveWhitelistRuleProgram>,
+    program_id: Pubkey,
+) -> Result<()> {
+    require!(
+        program_id != ctx.accounts.config.default_rule_program,
+        LazorKitError::InvalidRuleProgram
+    );
+
+    let whitelist_rule_programs = &mut ctx.accounts.whitelist_rule_programs;
+    require!(
+        whitelist_rule_programs.list.contains(&program_id),
+        LazorKitError::RuleProgramNotWhitelisted
+    );
+    whitelist_rule_programs
+        .list
+        .retain(|program| *program != program_id);
+
+    resize_account(
+        &whitelist_rule_programs.to_account_info(),
+        WhitelistRulePrograms::space(whitelist_rule_programs.list.len()),
+        &ctx.accounts.admin,
+        &ctx.accounts.system_program,
+    )
+}
+
+#[derive(Accounts)]
+pub struct RemoveWhitelistRuleProgram<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [Config::PREFIX_SEED],
+        bump,
+        owner = ID,
+        has_one = admin @ LazorKitError::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [WhitelistRulePrograms::PREFIX_SEED],
+        bump,
+        owner = ID,
+    )]
+    pub whitelist_rule_programs: Account<'info, WhitelistRulePrograms>,
+
+    pub system_program: Program<'info, System>,
+}
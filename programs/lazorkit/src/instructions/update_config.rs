@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::LazorKitError,
+    events::ConfigUpdated,
+    state::{Config, RelyingParty, WhitelistRulePrograms},
+    ID,
+};
+
+/// A single change to the program configuration
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub enum ConfigUpdate {
+    CreateSmartWalletFee(u64),
+    ExecuteFee(u64),
+    DefaultRuleProgram(Pubkey),
+    Treasury(Pubkey),
+    RelyingParties(Vec<RelyingParty>),
+}
+
+pub fn update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    match &update {
+        ConfigUpdate::CreateSmartWalletFee(fee) => config.create_smart_wallet_fee = *fee,
+        ConfigUpdate::ExecuteFee(fee) => config.execute_fee = *fee,
+        ConfigUpdate::DefaultRuleProgram(program) => {
+            require!(
+                ctx.accounts.whitelist_rule_programs.list.contains(program),
+                LazorKitError::RuleProgramNotWhitelisted
+            );
+            config.default_rule_program = *program;
+        }
+        ConfigUpdate::Treasury(treasury) => config.treasury = *treasury,
+        ConfigUpdate::RelyingParties(relying_parties) => {
+            RelyingParty::validate_set(relying_parties, Config::MAX_RELYING_PARTIES)?;
+            config.relying_parties = relying_parties.clone();
+        }
+    }
+
+    emit!(ConfigUpdated { update });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Config::PREFIX_SEED],
+        bump,
+        owner = ID,
+        has_one = admin @ LazorKitError::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [WhitelistRulePrograms::PREFIX_SEED],
+        bump,
+        owner = ID,
+    )]
+    pub whitelist_rule_programs: Box<Account<'info, WhitelistRulePrograms>>,
+}
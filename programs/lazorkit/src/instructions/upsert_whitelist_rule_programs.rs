@@ -1,13 +1,28 @@
 use anchor_lang::prelude::*;
 
-use crate::{state::WhitelistRulePrograms, ID};
+use crate::{
+    error::LazorKitError,
+    state::{Config, WhitelistRulePrograms},
+    utils::resize_account,
+    ID,
+};
 
+/// Add a program to the whitelist, doing nothing if it is already listed
 pub fn upsert_whitelist_rule_programs(
     ctx: Context<UpsertWhitelistRulePrograms>,
     hook: Pubkey,
 ) -> Result<()> {
     let whitelist_rule_programs = &mut ctx.accounts.whitelist_rule_programs;
+    if whitelist_rule_programs.list.contains(&hook) {
+        return Ok(());
+    }
 
+    resize_account(
+        &whitelist_rule_programs.to_account_info(),
+        WhitelistRulePrograms::space(whitelist_rule_programs.list.len() + 1),
+        &ctx.accounts.admin,
+        &ctx.accounts.system_program,
+    )?;
     whitelist_rule_programs.list.push(hook);
     Ok(())
 }
@@ -15,7 +30,15 @@ pub fn upsert_whitelist_rule_programs(
 #[derive(Accounts)]
 pub struct UpsertWhitelistRulePrograms<'info> {
     #[account(mut)]
-    pub signer: Signer<'info>,
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [Config::PREFIX_SEED],
+        bump,
+        owner = ID,
+        has_one = admin @ LazorKitError::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
 
     #[account(
         mut,
@@ -1,13 +1,20 @@
+// Anchor's `#[program]` expansion still calls the deprecated `AccountInfo::realloc`.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod message;
 pub mod state;
 pub mod utils;
+pub mod webauthn;
 
 use constants::PASSKEY_SIZE;
 use instructions::*;
+use state::RelyingParty;
 
 declare_id!("DAcTNgSppWiDvfTWa7PMvPmXHAs5DfBnrqRQme8fXJBb");
 
@@ -17,8 +24,8 @@ pub mod lazorkit {
     use super::*;
 
     /// Initialize the program by creating the sequence tracker
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        instructions::initialize(ctx)
+    pub fn initialize(ctx: Context<Initialize>, relying_parties: Vec<RelyingParty>) -> Result<()> {
+        instructions::initialize(ctx, relying_parties)
     }
 
     /// Create a new smart wallet with passkey authentication
@@ -26,18 +33,31 @@ pub mod lazorkit {
         ctx: Context<CreateSmartWallet>,
         passkey_pubkey: [u8; PASSKEY_SIZE],
         rule_data: Vec<u8>,
+        relying_parties: Option<Vec<RelyingParty>>,
     ) -> Result<()> {
-        instructions::create_smart_wallet(ctx, passkey_pubkey, rule_data)
+        instructions::create_smart_wallet(ctx, passkey_pubkey, rule_data, relying_parties)
     }
 
     /// Execute an instruction with passkey authentication
-    pub fn execute_instruction(
-        ctx: Context<ExecuteInstruction>,
+    pub fn execute_instruction<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, ExecuteInstruction<'info>>,
         args: ExecuteInstructionArgs,
     ) -> Result<()> {
         instructions::execute_instruction(ctx, args)
     }
 
+    /// Migrate a smart wallet created before versioned accounts to the current layout
+    pub fn migrate_smart_wallet<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateSmartWallet<'info>>,
+    ) -> Result<()> {
+        instructions::migrate_smart_wallet(ctx)
+    }
+
+    /// Migrate a legacy authenticator of an already migrated smart wallet
+    pub fn migrate_authenticator(ctx: Context<MigrateAuthenticator>) -> Result<()> {
+        instructions::migrate_authenticator(ctx)
+    }
+
     /// Update the list of whitelisted rule programs
     pub fn upsert_whitelist_rule_programs(
         ctx: Context<UpsertWhitelistRulePrograms>,
@@ -45,4 +65,27 @@ pub mod lazorkit {
     ) -> Result<()> {
         instructions::upsert_whitelist_rule_programs(ctx, program_id)
     }
+
+    /// Remove a program from the list of whitelisted rule programs
+    pub fn remove_whitelist_rule_program(
+        ctx: Context<RemoveWhitelistRuleProgram>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        instructions::remove_whitelist_rule_program(ctx, program_id)
+    }
+
+    /// Update a single program configuration value
+    pub fn update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> Result<()> {
+        instructions::update_config(ctx, update)
+    }
+
+    /// Propose a new protocol admin, who must accept before taking over
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    /// Accept a pending protocol admin transfer
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
 }
@@ -0,0 +1,171 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::hash::{hash, hashv},
+};
+
+use crate::{
+    error::LazorKitError,
+    instructions::{Action, BatchCpiData, CpiData, ExecuteInstructionArgs, Expiry, RuleAccounts},
+    state::{RelyingParty, VerificationPolicy},
+    ID,
+};
+
+/// Version of the [`ExecuteMessage`] layout, bumped whenever the layout changes
+pub const EXECUTE_MESSAGE_VERSION: u8 = 4;
+
+/// Canonical message a passkey signs to authorize a single `execute_instruction` call.
+///
+/// The program rebuilds this message from the instruction arguments and accounts, so a
+/// signature can only authorize the exact operation it was produced for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ExecuteMessage {
+    /// Layout version, always [`EXECUTE_MESSAGE_VERSION`]
+    pub version: u8,
+    /// Program the message is intended for
+    pub program_id: Pubkey,
+    /// Smart wallet the operation acts on
+    pub smart_wallet: Pubkey,
+    /// Action being authorized
+    pub action: Action,
+    /// Rule program targeted by the operation
+    pub rule_program: Pubkey,
+    /// Hash of the rule instruction data and its account metas
+    pub rule_hash: [u8; 32],
+    /// Hash of the program and accounts of every wallet rule, in the wallet's rule order
+    pub rule_hashes: Vec<[u8; 32]>,
+    /// Program invoked by the main CPI
+    pub cpi_program: Pubkey,
+    /// Hash of the main CPI data and its account metas, if any
+    pub cpi_hash: Option<[u8; 32]>,
+    /// Hash of every CPI of a batch, in execution order
+    pub batch_hashes: Vec<[u8; 32]>,
+    /// Passkey of the authenticator being added, if any
+    pub new_authenticator: Option<[u8; 33]>,
+    /// Passkey of the authenticator being revoked, if any
+    pub revoked_authenticator: Option<[u8; 33]>,
+    /// Verification policy being set, if any
+    pub new_verification_policy: Option<VerificationPolicy>,
+    /// Relying parties being set, if the action updates them
+    pub new_relying_parties: Option<Vec<RelyingParty>>,
+    /// Hash of the metas of every remaining account, in order, so that accounts an
+    /// action reads by position cannot be swapped after signing
+    pub remaining_accounts_hash: [u8; 32],
+    /// Smart wallet nonce the message is valid for
+    pub nonce: u64,
+    /// Optional slot or timestamp after which the message is rejected
+    pub expiry: Option<Expiry>,
+}
+
+impl ExecuteMessage {
+    /// Build the canonical message for the given arguments and accounts
+    pub fn new(
+        args: &ExecuteInstructionArgs,
+        smart_wallet: Pubkey,
+        rule_program: Pubkey,
+        cpi_program: Pubkey,
+        remaining_accounts: &[AccountInfo],
+    ) -> Result<Self> {
+        let cpi_hash = match &args.cpi_data {
+            Some(cpi_data) => Some(hash_cpi_data(cpi_data, remaining_accounts)?),
+            None => None,
+        };
+        let rule_hashes = args
+            .rules
+            .iter()
+            .map(|rule| hash_rule_accounts(rule, remaining_accounts))
+            .collect::<Result<Vec<_>>>()?;
+        let batch_hashes = args
+            .batch
+            .iter()
+            .map(|entry| hash_batch_cpi_data(entry, remaining_accounts))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            version: EXECUTE_MESSAGE_VERSION,
+            program_id: ID,
+            smart_wallet,
+            action: args.action.clone(),
+            rule_program,
+            rule_hash: hash_cpi_data(&args.rule_data, remaining_accounts)?,
+            rule_hashes,
+            cpi_program,
+            cpi_hash,
+            batch_hashes,
+            new_authenticator: args.create_new_authenticator,
+            revoked_authenticator: args.revoke_authenticator,
+            new_verification_policy: args.verification_policy,
+            new_relying_parties: args.relying_parties.clone(),
+            remaining_accounts_hash: hash_account_metas(remaining_accounts),
+            nonce: args.nonce,
+            expiry: args.expiry,
+        })
+    }
+
+    /// Serialized bytes that the passkey is expected to sign
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.try_to_vec()?)
+    }
+}
+
+/// Hash the metas of `accounts`, in order
+pub fn hash_account_metas(accounts: &[AccountInfo]) -> [u8; 32] {
+    let mut buf = Vec::with_capacity(accounts.len() * 34);
+    extend_with_account_metas(&mut buf, accounts);
+    hash(&buf).to_bytes()
+}
+
+/// Hash a CPI's instruction data together with the metas of the accounts it uses
+pub fn hash_cpi_data(cpi_data: &CpiData, remaining_accounts: &[AccountInfo]) -> Result<[u8; 32]> {
+    let accounts = cpi_data.accounts(remaining_accounts)?;
+
+    let mut buf = Vec::with_capacity(4 + cpi_data.data.len() + accounts.len() * 34);
+    buf.extend_from_slice(&(cpi_data.data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&cpi_data.data);
+    extend_with_account_metas(&mut buf, accounts);
+    Ok(hash(&buf).to_bytes())
+}
+
+/// Hash a wallet rule's program together with the metas of the accounts it uses
+pub fn hash_rule_accounts(
+    rule: &RuleAccounts,
+    remaining_accounts: &[AccountInfo],
+) -> Result<[u8; 32]> {
+    let program = remaining_accounts
+        .get(rule.program_index as usize)
+        .ok_or(LazorKitError::InvalidAccountInput)?;
+    let accounts = rule.accounts(remaining_accounts)?;
+
+    let mut buf = Vec::with_capacity(32 + accounts.len() * 34);
+    buf.extend_from_slice(program.key.as_ref());
+    extend_with_account_metas(&mut buf, accounts);
+    Ok(hash(&buf).to_bytes())
+}
+
+fn extend_with_account_metas(buf: &mut Vec<u8>, accounts: &[AccountInfo]) {
+    for acc in accounts {
+        buf.extend_from_slice(acc.key.as_ref());
+        buf.push(acc.is_signer as u8);
+        buf.push(acc.is_writable as u8);
+    }
+}
+
+/// Hash a batch entry: the invoked program, its rule checks and its instruction
+pub fn hash_batch_cpi_data(
+    entry: &BatchCpiData,
+    remaining_accounts: &[AccountInfo],
+) -> Result<[u8; 32]> {
+    let program = remaining_accounts
+        .get(entry.program_index as usize)
+        .ok_or(LazorKitError::InvalidAccountInput)?;
+    let rule_hashes = entry
+        .rules
+        .iter()
+        .map(|rule| hash_rule_accounts(rule, remaining_accounts))
+        .collect::<Result<Vec<_>>>()?;
+    let cpi_hash = hash_cpi_data(&entry.cpi_data, remaining_accounts)?;
+
+    let mut parts = vec![program.key.as_ref()];
+    parts.extend(rule_hashes.iter().map(|hash| hash.as_ref()));
+    parts.push(&cpi_hash);
+    Ok(hashv(&parts).to_bytes())
+}
@@ -1,13 +1,28 @@
 use anchor_lang::prelude::*;
 
+use super::RelyingParty;
+
 #[account]
 #[derive(Default, InitSpace)]
 pub struct Config {
+    /// Protocol admin allowed to manage the whitelist and configuration
+    pub admin: Pubkey,
+    /// Admin proposed by the current admin, pending acceptance
+    pub pending_admin: Option<Pubkey>,
+    /// Lamports charged to a new smart wallet, paid to the treasury
     pub create_smart_wallet_fee: u64,
+    /// Lamports charged to the smart wallet for every execute_instruction
+    pub execute_fee: u64,
+    /// Account receiving protocol fees
+    pub treasury: Pubkey,
     pub default_rule_program: Pubkey,
     pub authority_bump: u8,
+    /// Relying parties accepted for every wallet without its own override
+    #[max_len(8)]
+    pub relying_parties: Vec<RelyingParty>,
 }
 
 impl Config {
     pub const PREFIX_SEED: &'static [u8] = b"config";
+    pub const MAX_RELYING_PARTIES: usize = 8;
 }
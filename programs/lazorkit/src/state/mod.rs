@@ -1,11 +1,15 @@
 mod config;
+mod relying_party;
 mod smart_wallet_authenticator;
 mod smart_wallet_config;
 mod smart_wallet_seq;
+mod verification_policy;
 mod whitelist_rule_programs;
 
 pub use config::*;
+pub use relying_party::*;
 pub use smart_wallet_authenticator::*;
 pub use smart_wallet_config::*;
 pub use smart_wallet_seq::*;
+pub use verification_policy::*;
 pub use whitelist_rule_programs::*;
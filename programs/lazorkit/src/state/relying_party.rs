@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::error::LazorKitError;
+
+/// A relying party (front-end) whose passkey assertions are accepted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct RelyingParty {
+    /// SHA-256 hash of the relying party id, as found in the authenticator data
+    pub rp_id_hash: [u8; 32],
+    /// Origin reported in the clientDataJSON, e.g. `https://app.example.com`
+    #[max_len(64)]
+    pub origin: String,
+}
+
+impl RelyingParty {
+    pub const MAX_ORIGIN_LEN: usize = 64;
+
+    /// Check that a relying party set is non-empty and fits in `max_len` entries
+    pub fn validate_set(set: &[RelyingParty], max_len: usize) -> Result<()> {
+        require!(
+            !set.is_empty()
+                && set.len() <= max_len
+                && set
+                    .iter()
+                    .all(|rp| !rp.origin.is_empty() && rp.origin.len() <= Self::MAX_ORIGIN_LEN),
+            LazorKitError::InvalidRelyingParties
+        );
+        Ok(())
+    }
+}
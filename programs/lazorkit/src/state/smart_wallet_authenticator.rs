@@ -9,10 +9,53 @@ pub struct SmartWalletAuthenticator {
     pub passkey_pubkey: [u8; PASSKEY_SIZE],
     /// The smart wallet this authenticator belongs to
     pub smart_wallet: Pubkey,
+    /// Last WebAuthn signature counter reported by the passkey
+    pub sign_count: u32,
+    /// Set once the passkey reported a counter that did not increase, barring the
+    /// authenticator from any further use
+    pub clone_suspected: bool,
     /// Bump seed for PDA derivation
     pub bump: u8,
 }
 
 impl SmartWalletAuthenticator {
     pub const PREFIX_SEED: &'static [u8] = b"smart_wallet_authenticator";
+
+    /// Whether `sign_count` may follow the stored counter. Authenticators that always
+    /// report zero, such as synced passkeys, do not track a counter.
+    pub fn is_valid_sign_count(&self, sign_count: u32) -> bool {
+        (sign_count == 0 && self.sign_count == 0) || sign_count > self.sign_count
+    }
+}
+
+/// Layout of authenticators created before the signature counter was tracked, converted
+/// to the current layout by `migrate_smart_wallet` or `migrate_authenticator`
+#[derive(AnchorDeserialize)]
+pub struct LegacySmartWalletAuthenticator {
+    pub passkey_pubkey: [u8; PASSKEY_SIZE],
+    pub smart_wallet: Pubkey,
+    pub bump: u8,
+}
+
+impl LegacySmartWalletAuthenticator {
+    pub const SPACE: usize = 8 + PASSKEY_SIZE + 32 + 1;
+
+    /// The legacy authenticator held in `data`, if `data` is in the legacy layout
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::SPACE || !data.starts_with(SmartWalletAuthenticator::DISCRIMINATOR) {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The authenticator in the current layout, with no signature counter seen yet
+    pub fn migrate(self) -> SmartWalletAuthenticator {
+        SmartWalletAuthenticator {
+            passkey_pubkey: self.passkey_pubkey,
+            smart_wallet: self.smart_wallet,
+            sign_count: 0,
+            clone_suspected: false,
+            bump: self.bump,
+        }
+    }
 }
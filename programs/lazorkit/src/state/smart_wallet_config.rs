@@ -1,17 +1,216 @@
 use anchor_lang::prelude::*;
 
+use super::{Config, RelyingParty, VerificationPolicy};
+use crate::error::LazorKitError;
+
 /// Data account for a smart wallet
 #[account]
 #[derive(Default, InitSpace)]
 pub struct SmartWalletConfig {
+    /// Layout version, [`SmartWalletConfig::VERSION`] for accounts in the current layout
+    pub version: u8,
     /// Unique identifier for this smart wallet
     pub id: u64,
-    /// Optional rule program that governs this wallet's operations
-    pub rule_program: Pubkey,
+    /// Rule programs governing this wallet, in the order their checks run
+    #[max_len(4)]
+    pub rule_programs: Vec<Pubkey>,
+    /// Nonce expected by the next execute_instruction, incremented on every execution
+    pub nonce: u64,
+    /// Number of authenticators that can sign for this wallet
+    pub authenticator_count: u16,
+    /// Relying parties accepted for this wallet, overriding the global set when present
+    #[max_len(4)]
+    pub relying_parties: Option<Vec<RelyingParty>>,
+    /// Which actions require user verification rather than just user presence
+    pub verification_policy: VerificationPolicy,
     /// Bump seed for PDA derivation
     pub bump: u8,
 }
 
 impl SmartWalletConfig {
     pub const PREFIX_SEED: &'static [u8] = b"smart_wallet_config";
+    pub const MAX_RELYING_PARTIES: usize = 4;
+    pub const MAX_RULE_PROGRAMS: usize = 4;
+    pub const VERSION: u8 = 1;
+
+    /// Relying parties accepted for this wallet
+    pub fn allowed_relying_parties<'a>(&'a self, config: &'a Config) -> &'a [RelyingParty] {
+        self.relying_parties
+            .as_deref()
+            .unwrap_or(&config.relying_parties)
+    }
+
+    /// Whether `program` is one of the rule programs this wallet opted into
+    pub fn has_rule_program(&self, program: &Pubkey) -> bool {
+        self.rule_programs.contains(program)
+    }
+
+    /// Replace the rule program `old` with `new`, keeping the order in which rules run
+    pub fn replace_rule_program(&mut self, old: &Pubkey, new: Pubkey) -> Result<()> {
+        require!(
+            !self.has_rule_program(&new),
+            LazorKitError::RuleProgramAlreadyAttached
+        );
+        let position = self
+            .rule_programs
+            .iter()
+            .position(|program| program == old)
+            .ok_or(LazorKitError::InvalidRuleProgram)?;
+        self.rule_programs[position] = new;
+        Ok(())
+    }
+}
+
+/// Layout of smart wallet configs created before the version byte, converted to the
+/// current layout by `migrate_smart_wallet`
+#[derive(AnchorDeserialize, Debug, PartialEq, Eq)]
+pub struct LegacySmartWalletConfig {
+    pub id: u64,
+    pub rule_program: Pubkey,
+    pub bump: u8,
+}
+
+impl LegacySmartWalletConfig {
+    pub const SPACE: usize = 8 + 8 + 32 + 1;
+
+    /// The legacy config held in `data`, if `data` is in the legacy layout
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::SPACE || !data.starts_with(SmartWalletConfig::DISCRIMINATOR) {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The config in the current layout, counting the `authenticator_count` authenticators
+    /// migrated with it
+    pub fn migrate(self, authenticator_count: u16) -> SmartWalletConfig {
+        SmartWalletConfig {
+            version: SmartWalletConfig::VERSION,
+            id: self.id,
+            rule_programs: vec![self.rule_program],
+            nonce: 0,
+            authenticator_count,
+            relying_parties: None,
+            verification_policy: VerificationPolicy::default(),
+            bump: self.bump,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SmartWalletAuthenticator;
+
+    const DEFAULT_RULE_ID: Pubkey = pubkey!("B98ooLRYBP6m6Zsrd3Hnzn4UAejfVZwyDgMFaBNzVR2W");
+    const TRANSFER_LIMIT_ID: Pubkey = pubkey!("HjgdxTNPqpL59KLRVDwQ28cqam2SxBirnNN5SFAFGHZ8");
+    const THIRD_RULE_ID: Pubkey = pubkey!("RuLe111111111111111111111111111111111111111");
+
+    fn wallet_on(rule_programs: &[Pubkey]) -> SmartWalletConfig {
+        SmartWalletConfig {
+            rule_programs: rule_programs.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn transfer_limit_wallet_accepts_transfer_limit() {
+        assert!(wallet_on(&[TRANSFER_LIMIT_ID]).has_rule_program(&TRANSFER_LIMIT_ID));
+    }
+
+    #[test]
+    fn transfer_limit_wallet_rejects_default_rule() {
+        assert!(!wallet_on(&[TRANSFER_LIMIT_ID]).has_rule_program(&DEFAULT_RULE_ID));
+    }
+
+    #[test]
+    fn default_rule_wallet_rejects_transfer_limit() {
+        assert!(!wallet_on(&[DEFAULT_RULE_ID]).has_rule_program(&TRANSFER_LIMIT_ID));
+    }
+
+    #[test]
+    fn multi_rule_wallet_accepts_each_of_its_rules() {
+        let wallet = wallet_on(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+        assert!(wallet.has_rule_program(&DEFAULT_RULE_ID));
+        assert!(wallet.has_rule_program(&TRANSFER_LIMIT_ID));
+        assert!(!wallet.has_rule_program(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn migrates_through_a_chain_of_three_rules() {
+        let mut wallet = wallet_on(&[DEFAULT_RULE_ID]);
+        for (old, new) in [
+            (DEFAULT_RULE_ID, TRANSFER_LIMIT_ID),
+            // Custom to custom, without going back through the default rule
+            (TRANSFER_LIMIT_ID, THIRD_RULE_ID),
+            (THIRD_RULE_ID, DEFAULT_RULE_ID),
+        ] {
+            wallet.replace_rule_program(&old, new).unwrap();
+            assert_eq!(wallet.rule_programs, [new]);
+        }
+    }
+
+    #[test]
+    fn migration_keeps_the_order_of_other_rules() {
+        let mut wallet = wallet_on(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+        wallet
+            .replace_rule_program(&DEFAULT_RULE_ID, THIRD_RULE_ID)
+            .unwrap();
+        assert_eq!(wallet.rule_programs, [THIRD_RULE_ID, TRANSFER_LIMIT_ID]);
+    }
+
+    #[test]
+    fn migration_rejects_attached_or_unknown_rules() {
+        let mut wallet = wallet_on(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+        // The new rule is already attached, including migrating a rule to itself
+        assert!(wallet
+            .replace_rule_program(&DEFAULT_RULE_ID, TRANSFER_LIMIT_ID)
+            .is_err());
+        assert!(wallet
+            .replace_rule_program(&DEFAULT_RULE_ID, DEFAULT_RULE_ID)
+            .is_err());
+        // The old rule is not one of the wallet's
+        assert!(wallet
+            .replace_rule_program(&THIRD_RULE_ID, Pubkey::new_unique())
+            .is_err());
+        assert_eq!(wallet.rule_programs, [DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+    }
+
+    fn legacy_data(id: u64, rule_program: Pubkey, bump: u8) -> Vec<u8> {
+        [
+            SmartWalletConfig::DISCRIMINATOR,
+            &id.to_le_bytes(),
+            rule_program.as_ref(),
+            &[bump],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn legacy_config_migrates_to_the_current_layout() {
+        let legacy = LegacySmartWalletConfig::parse(&legacy_data(7, DEFAULT_RULE_ID, 254)).unwrap();
+        let config = legacy.migrate(2);
+        assert_eq!(config.version, SmartWalletConfig::VERSION);
+        assert_eq!(config.id, 7);
+        assert_eq!(config.rule_programs, [DEFAULT_RULE_ID]);
+        assert_eq!(config.authenticator_count, 2);
+        assert_eq!(config.nonce, 0);
+        assert_eq!(config.bump, 254);
+    }
+
+    #[test]
+    fn only_the_legacy_layout_is_migrated() {
+        let mut current = vec![];
+        wallet_on(&[DEFAULT_RULE_ID])
+            .try_serialize(&mut current)
+            .unwrap();
+        assert!(LegacySmartWalletConfig::parse(&current).is_none());
+
+        let mut other_account = legacy_data(7, DEFAULT_RULE_ID, 254);
+        other_account[..8].copy_from_slice(SmartWalletAuthenticator::DISCRIMINATOR);
+        assert!(LegacySmartWalletConfig::parse(&other_account).is_none());
+
+        let data = legacy_data(7, DEFAULT_RULE_ID, 254);
+        assert!(LegacySmartWalletConfig::parse(&data[..data.len() - 1]).is_none());
+    }
 }
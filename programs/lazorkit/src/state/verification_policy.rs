@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::Action;
+
+/// Policy deciding which actions need user verification (biometric or PIN) on top of
+/// user presence
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct VerificationPolicy {
+    /// Bitmask of [`Action::mask`] values that require user verification
+    pub uv_required_actions: u16,
+    /// Lamports an `ExecuteCpi` or `ExecuteBatch` made only of SOL transfers may move out
+    /// of the wallet with user presence only. Any other CPI requires user verification,
+    /// as its outflow is not measured in lamports.
+    pub execute_cpi_uv_threshold: u64,
+}
+
+impl VerificationPolicy {
+    /// Actions that require user verification regardless of the configured policy
+    pub const ALWAYS_UV_ACTIONS: u16 = Action::ChangeProgramRule.mask()
+        | Action::CallRuleProgram.mask()
+        | Action::UpdateVerificationPolicy.mask()
+        | Action::RevokeAuthenticator.mask()
+        | Action::AddRuleProgram.mask()
+        | Action::RemoveRuleProgram.mask()
+        | Action::UpdateRelyingParties.mask();
+
+    /// Whether `action` requires user verification before it runs
+    pub fn requires_uv(&self, action: &Action) -> bool {
+        (self.uv_required_actions | Self::ALWAYS_UV_ACTIONS) & action.mask() != 0
+    }
+
+    /// Whether an `ExecuteCpi` or `ExecuteBatch` that moved `outflow` lamports requires
+    /// user verification, given whether all of its CPIs were SOL transfers
+    pub fn requires_uv_for_execute(&self, outflow: u64, sol_transfers_only: bool) -> bool {
+        !sol_transfers_only || outflow > self.execute_cpi_uv_threshold
+    }
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        Self {
+            uv_required_actions: Self::ALWAYS_UV_ACTIONS,
+            execute_cpi_uv_threshold: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(uv_required_actions: u16, execute_cpi_uv_threshold: u64) -> VerificationPolicy {
+        VerificationPolicy {
+            uv_required_actions,
+            execute_cpi_uv_threshold,
+        }
+    }
+
+    #[test]
+    fn wallet_changes_always_require_uv() {
+        let policy = policy(0, u64::MAX);
+        assert!(policy.requires_uv(&Action::UpdateVerificationPolicy));
+        assert!(policy.requires_uv(&Action::ChangeProgramRule));
+        assert!(policy.requires_uv(&Action::UpdateRelyingParties));
+        assert!(!policy.requires_uv(&Action::ExecuteCpi));
+        assert!(!policy.requires_uv(&Action::CheckAuthenticator));
+    }
+
+    #[test]
+    fn configured_actions_require_uv() {
+        let policy = policy(Action::ExecuteCpi.mask(), u64::MAX);
+        assert!(policy.requires_uv(&Action::ExecuteCpi));
+        assert!(!policy.requires_uv(&Action::ExecuteBatch));
+    }
+
+    #[test]
+    fn sol_transfers_require_uv_above_the_threshold() {
+        let policy = policy(0, 1_000);
+        assert!(!policy.requires_uv_for_execute(0, true));
+        assert!(!policy.requires_uv_for_execute(1_000, true));
+        assert!(policy.requires_uv_for_execute(1_001, true));
+    }
+
+    #[test]
+    fn other_cpis_require_uv_whatever_their_lamport_outflow() {
+        let policy = policy(0, u64::MAX);
+        assert!(policy.requires_uv_for_execute(0, false));
+        assert!(policy.requires_uv_for_execute(u64::MAX, false));
+    }
+}
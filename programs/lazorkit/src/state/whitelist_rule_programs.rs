@@ -1,11 +1,14 @@
 use anchor_lang::prelude::*;
 
-/// Account that stores whitelisted rule program addresses
+/// Account that stores whitelisted rule program addresses.
+///
+/// The account is resized as programs are added or removed. Wallets whose rule program
+/// is removed can no longer execute through it, but can still change to a whitelisted
+/// rule. The removed program is still called to destroy its rule, and must succeed.
 #[account]
-#[derive(Debug, InitSpace)]
+#[derive(Debug)]
 pub struct WhitelistRulePrograms {
-    /// List of whitelisted program addresses
-    #[max_len(10)]
+    /// List of whitelisted program addresses, without duplicates
     pub list: Vec<Pubkey>,
     /// Bump seed for PDA derivation
     pub bump: u8,
@@ -13,4 +16,9 @@ pub struct WhitelistRulePrograms {
 
 impl WhitelistRulePrograms {
     pub const PREFIX_SEED: &'static [u8] = b"whitelist_rule_programs";
+
+    /// Account size, including discriminator, for a list of `len` programs
+    pub const fn space(len: usize) -> usize {
+        8 + 4 + 32 * len + 1
+    }
 }
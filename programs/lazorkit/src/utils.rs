@@ -1,17 +1,26 @@
-use crate::constants::SECP256R1_ID;
+use crate::constants::{SECP256R1_ID, TOKEN_2022_PROGRAM_ID, TOKEN_ACCOUNT_SIZE, TOKEN_PROGRAM_ID};
 use crate::{error::LazorKitError, ID};
 use anchor_lang::solana_program::{
     instruction::Instruction,
     program::{invoke, invoke_signed},
+    sysvar::instructions::load_instruction_at_checked,
 };
-use anchor_lang::{prelude::*, solana_program::hash::hash};
+use anchor_lang::{prelude::*, solana_program::hash::hash, system_program};
+use std::borrow::Cow;
 
 // Constants for Secp256r1 signature verification
-const SECP_HEADER_SIZE: u16 = 14;
-const SECP_DATA_START: u16 = 2 + SECP_HEADER_SIZE;
+const SECP_OFFSETS_START: usize = 2;
+const SECP_OFFSETS_SIZE: usize = 14;
 const SECP_PUBKEY_SIZE: u16 = 33;
 const SECP_SIGNATURE_SIZE: u16 = 64;
-const SECP_HEADER_TOTAL: usize = 16;
+/// Instruction index referring to the precompile instruction itself
+const SECP_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Half of the secp256r1 curve order, the largest accepted `s` value of a signature
+const SECP256R1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xde, 0x73, 0x7d, 0x56, 0xd3, 0x8b, 0xcf, 0x42, 0x79, 0xdc, 0xe5, 0x61, 0x7e, 0x31, 0x92, 0xa8,
+];
 
 /// Represents a Program Derived Address signer with its seeds and bump
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -45,7 +54,7 @@ pub fn execute_cpi(
 }
 
 /// Create a CPI instruction with proper account meta configuration
-fn create_cpi_instruction(
+pub fn create_cpi_instruction(
     accounts: &[AccountInfo],
     data: Vec<u8>,
     program: &AccountInfo,
@@ -75,82 +84,132 @@ fn create_cpi_instruction(
     }
 }
 
-/// Verify a Secp256r1 signature instruction
+/// Verify that a Secp256r1 precompile instruction checked `sig` by `pubkey` over `msg`.
+///
+/// The precompile may verify several signatures, and each of their components may live in
+/// any instruction of the transaction, which is loaded through `ix_sysvar`.
 pub fn verify_secp256r1_instruction(
     ix: &Instruction,
+    ix_sysvar: &AccountInfo,
     pubkey: [u8; SECP_PUBKEY_SIZE as usize],
     msg: Vec<u8>,
     sig: Vec<u8>,
 ) -> Result<()> {
-    let expected_len =
-        (SECP_DATA_START + SECP_PUBKEY_SIZE + SECP_SIGNATURE_SIZE) as usize + msg.len();
-    if ix.program_id != SECP256R1_ID || !ix.accounts.is_empty() || ix.data.len() != expected_len {
+    if ix.program_id != SECP256R1_ID || !ix.accounts.is_empty() {
         return Err(LazorKitError::InvalidLengthForVerification.into());
     }
-    verify_secp256r1_data(&ix.data, pubkey, msg, sig)
+    verify_secp256r1_data(
+        &ix.data,
+        |index| Ok(load_instruction_at_checked(index as usize, ix_sysvar)?.data),
+        pubkey,
+        msg,
+        sig,
+    )
 }
 
-/// Verify the data portion of a Secp256r1 signature
+/// Find the signature entry for `public_key` and `message` in the precompile data and
+/// check it against `signature`
 fn verify_secp256r1_data(
     data: &[u8],
+    load_instruction_data: impl Fn(u16) -> Result<Vec<u8>>,
     public_key: [u8; SECP_PUBKEY_SIZE as usize],
     message: Vec<u8>,
     signature: Vec<u8>,
 ) -> Result<()> {
-    let msg_len = message.len() as u16;
-    let offsets = calculate_secp_offsets(msg_len);
-
-    if !verify_secp_header(data, &offsets) {
+    let num_signatures = *data
+        .first()
+        .ok_or(LazorKitError::InvalidLengthForVerification)? as usize;
+    if num_signatures == 0 {
         return Err(LazorKitError::VerifyHeaderMismatchError.into());
     }
+    let offsets_end = SECP_OFFSETS_START + num_signatures * SECP_OFFSETS_SIZE;
+    let offsets_data = data
+        .get(SECP_OFFSETS_START..offsets_end)
+        .ok_or(LazorKitError::InvalidLengthForVerification)?;
+
+    let read = |index: u16, offset: u16, len: usize| -> Result<Cow<[u8]>> {
+        let range = offset as usize..offset as usize + len;
+        let bytes = if index == SECP_CURRENT_INSTRUCTION {
+            data.get(range).map(Cow::Borrowed)
+        } else {
+            load_instruction_data(index)?
+                .get(range)
+                .map(|bytes| Cow::Owned(bytes.to_vec()))
+        };
+        bytes.ok_or(LazorKitError::InvalidLengthForVerification.into())
+    };
+
+    for entry in offsets_data.chunks_exact(SECP_OFFSETS_SIZE) {
+        let offsets = SecpOffsets::parse(entry);
+
+        let entry_pubkey = read(
+            offsets.public_key_instruction_index,
+            offsets.public_key_offset,
+            SECP_PUBKEY_SIZE as usize,
+        )?;
+        if *entry_pubkey != public_key[..] {
+            continue;
+        }
+        let entry_message = read(
+            offsets.message_instruction_index,
+            offsets.message_data_offset,
+            offsets.message_data_size as usize,
+        )?;
+        if *entry_message != message[..] {
+            continue;
+        }
 
-    if !verify_secp_data(data, &public_key, &signature, &message) {
-        return Err(LazorKitError::VerifyDataMismatchError.into());
+        let entry_signature = read(
+            offsets.signature_instruction_index,
+            offsets.signature_offset,
+            SECP_SIGNATURE_SIZE as usize,
+        )?;
+        if *entry_signature != signature[..] {
+            return Err(LazorKitError::VerifyDataMismatchError.into());
+        }
+
+        // Both (r, s) and (r, n - s) verify, so only the low-S form is accepted
+        if !is_low_s(&signature) {
+            return Err(LazorKitError::HighSSignature.into());
+        }
+        return Ok(());
     }
 
-    Ok(())
+    Err(LazorKitError::VerifyDataMismatchError.into())
 }
 
-/// Calculate offsets for Secp256r1 signature verification
+/// One `Secp256r1SignatureOffsets` entry of the precompile instruction data
 #[derive(Debug)]
 struct SecpOffsets {
-    pubkey_offset: u16,
-    sig_offset: u16,
-    msg_offset: u16,
-    msg_len: u16,
+    signature_offset: u16,
+    signature_instruction_index: u16,
+    public_key_offset: u16,
+    public_key_instruction_index: u16,
+    message_data_offset: u16,
+    message_data_size: u16,
+    message_instruction_index: u16,
 }
 
-#[inline]
-fn calculate_secp_offsets(msg_len: u16) -> SecpOffsets {
-    SecpOffsets {
-        pubkey_offset: SECP_DATA_START,
-        sig_offset: SECP_DATA_START + SECP_PUBKEY_SIZE,
-        msg_offset: SECP_DATA_START + SECP_PUBKEY_SIZE + SECP_SIGNATURE_SIZE,
-        msg_len,
+impl SecpOffsets {
+    #[inline]
+    fn parse(entry: &[u8]) -> Self {
+        let field = |i: usize| u16::from_le_bytes([entry[2 * i], entry[2 * i + 1]]);
+        Self {
+            signature_offset: field(0),
+            signature_instruction_index: field(1),
+            public_key_offset: field(2),
+            public_key_instruction_index: field(3),
+            message_data_offset: field(4),
+            message_data_size: field(5),
+            message_instruction_index: field(6),
+        }
     }
 }
 
+/// Check that a 64-byte `r || s` signature has `s` in the lower half of the curve order
 #[inline]
-fn verify_secp_header(data: &[u8], offsets: &SecpOffsets) -> bool {
-    data[0] == 1
-        && u16::from_le_bytes(data[2..=3].try_into().unwrap()) == offsets.sig_offset
-        && u16::from_le_bytes(data[4..=5].try_into().unwrap()) == 0xFFFF
-        && u16::from_le_bytes(data[6..=7].try_into().unwrap()) == offsets.pubkey_offset
-        && u16::from_le_bytes(data[8..=9].try_into().unwrap()) == 0xFFFF
-        && u16::from_le_bytes(data[10..=11].try_into().unwrap()) == offsets.msg_offset
-        && u16::from_le_bytes(data[12..=13].try_into().unwrap()) == offsets.msg_len
-        && u16::from_le_bytes(data[14..=15].try_into().unwrap()) == 0xFFFF
-}
-
-#[inline]
-fn verify_secp_data(data: &[u8], public_key: &[u8], signature: &[u8], message: &[u8]) -> bool {
-    let pubkey_range = SECP_HEADER_TOTAL..SECP_HEADER_TOTAL + SECP_PUBKEY_SIZE as usize;
-    let sig_range = pubkey_range.end..pubkey_range.end + SECP_SIGNATURE_SIZE as usize;
-    let msg_range = sig_range.end..;
-
-    data[pubkey_range] == public_key[..]
-        && data[sig_range] == signature[..]
-        && data[msg_range] == message[..]
+pub fn is_low_s(signature: &[u8]) -> bool {
+    signature.len() == SECP_SIGNATURE_SIZE as usize && signature[32..] <= SECP256R1_HALF_ORDER[..]
 }
 
 /// Extension trait for passkey operations
@@ -168,24 +227,144 @@ impl PasskeyExt for [u8; SECP_PUBKEY_SIZE as usize] {
     }
 }
 
+/// Amount held by an SPL Token or Token-2022 token account
+pub fn token_account_amount(account: &AccountInfo) -> Result<u64> {
+    require!(
+        *account.owner == TOKEN_PROGRAM_ID || *account.owner == TOKEN_2022_PROGRAM_ID,
+        LazorKitError::InvalidTokenAccount
+    );
+    let data = account.try_borrow_data()?;
+    // Token-2022 accounts with extensions store their account type right after the base
+    // layout, which tells them apart from mints of the same size
+    const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+    require!(
+        data.len() == TOKEN_ACCOUNT_SIZE
+            || data.get(TOKEN_ACCOUNT_SIZE) == Some(&ACCOUNT_TYPE_ACCOUNT),
+        LazorKitError::InvalidTokenAccount
+    );
+    Ok(u64::from_le_bytes(data[64..72].try_into().unwrap()))
+}
+
 /// Transfer SOL from a PDA-owned account
 #[inline]
 pub fn transfer_sol_from_pda(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
     // Debit from source account
-    **from.try_borrow_mut_lamports()? -= amount;
+    let from_balance = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(LazorKitError::InsufficientFunds)?;
     // Credit to destination account
-    **to.try_borrow_mut_lamports()? += amount;
+    let to_balance = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(LazorKitError::InvalidAccountInput)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
     Ok(())
 }
 
-/// Helper to get sighash for anchor instructions
-pub fn sighash(namespace: &str, name: &str) -> [u8; 8] {
-    let preimage = format!("{}:{}", namespace, name);
-    let mut out = [0u8; 8];
-    out.copy_from_slice(
-        &anchor_lang::solana_program::hash::hash(preimage.as_bytes()).to_bytes()[..8],
-    );
-    out
+/// Create the PDA `account` with `space` bytes of data owned by `owner`, the program
+/// signing with `signer_seeds`, and fund it with rent from `payer`. Like Anchor's `init`,
+/// an account already holding lamports is topped up, allocated and assigned instead
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let balance = account.lamports();
+    if balance == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    if rent > balance {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent - balance,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        owner,
+    )
+}
+
+/// Resize an account owned by this program, taking the extra rent from `payer` when it
+/// grows and refunding the excess rent to `payer` when it shrinks
+pub fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    new_size: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(new_size);
+    let balance = account.lamports();
+    if rent > balance {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent - balance,
+        )?;
+    } else {
+        transfer_sol_from_pda(account, payer, balance - rent)?;
+    }
+    account.resize(new_size)?;
+    Ok(())
+}
+
+/// Resize `account` to `space` and overwrite it with `value`
+pub fn write_account<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    value: &T,
+    space: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    resize_account(account, space, payer, system_program)?;
+    let mut data = account.try_borrow_mut_data()?;
+    value.try_serialize(&mut &mut data[..])
 }
 
 /// Helper: Get a slice of accounts from remaining_accounts
@@ -218,3 +397,196 @@ pub fn check_whitelist(
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6979 A.2.5: P-256, SHA-256, message "sample"
+    const PUBKEY: [u8; 33] = [
+        0x03, 0x60, 0xfe, 0xd4, 0xba, 0x25, 0x5a, 0x9d, 0x31, 0xc9, 0x61, 0xeb, 0x74, 0xc6, 0x35,
+        0x6d, 0x68, 0xc0, 0x49, 0xb8, 0x92, 0x3b, 0x61, 0xfa, 0x6c, 0xe6, 0x69, 0x62, 0x2e, 0x60,
+        0xf2, 0x9f, 0xb6,
+    ];
+    const R: [u8; 32] = [
+        0xef, 0xd4, 0x8b, 0x2a, 0xac, 0xb6, 0xa8, 0xfd, 0x11, 0x40, 0xdd, 0x9c, 0xd4, 0x5e, 0x81,
+        0xd6, 0x9d, 0x2c, 0x87, 0x7b, 0x56, 0xaa, 0xf9, 0x91, 0xc3, 0x4d, 0x0e, 0xa8, 0x4e, 0xaf,
+        0x37, 0x16,
+    ];
+    const HIGH_S: [u8; 32] = [
+        0xf7, 0xcb, 0x1c, 0x94, 0x2d, 0x65, 0x7c, 0x41, 0xd4, 0x36, 0xc7, 0xa1, 0xb6, 0xe2, 0x9f,
+        0x65, 0xf3, 0xe9, 0x00, 0xdb, 0xb9, 0xaf, 0xf4, 0x06, 0x4d, 0xc4, 0xab, 0x2f, 0x84, 0x3a,
+        0xcd, 0xa8,
+    ];
+    // n - HIGH_S
+    const LOW_S: [u8; 32] = [
+        0x08, 0x34, 0xe3, 0x6a, 0xd2, 0x9a, 0x83, 0xbf, 0x2b, 0xc9, 0x38, 0x5e, 0x49, 0x1d, 0x60,
+        0x99, 0xc8, 0xfd, 0xf9, 0xd1, 0xed, 0x67, 0xaa, 0x7e, 0xa5, 0xf5, 0x1f, 0x93, 0x78, 0x28,
+        0x57, 0xa9,
+    ];
+    const MESSAGE: &[u8] = b"sample";
+
+    fn signature(s: &[u8; 32]) -> Vec<u8> {
+        [R.as_slice(), s.as_slice()].concat()
+    }
+
+    /// Precompile data holding `signatures` inline, each entry as (pubkey, signature, message)
+    fn secp_data(signatures: &[(&[u8], &[u8], &[u8])]) -> Vec<u8> {
+        let mut data = vec![signatures.len() as u8, 0];
+        let mut payload = vec![];
+        let payload_start = SECP_OFFSETS_START + signatures.len() * SECP_OFFSETS_SIZE;
+        for (pubkey, sig, msg) in signatures {
+            let pubkey_offset = (payload_start + payload.len()) as u16;
+            payload.extend_from_slice(pubkey);
+            let sig_offset = (payload_start + payload.len()) as u16;
+            payload.extend_from_slice(sig);
+            let msg_offset = (payload_start + payload.len()) as u16;
+            payload.extend_from_slice(msg);
+            for value in [
+                sig_offset,
+                SECP_CURRENT_INSTRUCTION,
+                pubkey_offset,
+                SECP_CURRENT_INSTRUCTION,
+                msg_offset,
+                msg.len() as u16,
+                SECP_CURRENT_INSTRUCTION,
+            ] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&payload);
+        data
+    }
+
+    fn no_instructions(_: u16) -> Result<Vec<u8>> {
+        Err(ProgramError::InvalidArgument.into())
+    }
+
+    fn verify(data: &[u8], sig: Vec<u8>) -> Result<()> {
+        verify_secp256r1_data(data, no_instructions, PUBKEY, MESSAGE.to_vec(), sig)
+    }
+
+    #[test]
+    fn is_low_s_checks_half_order() {
+        assert!(is_low_s(&signature(&LOW_S)));
+        assert!(!is_low_s(&signature(&HIGH_S)));
+        assert!(is_low_s(&signature(&SECP256R1_HALF_ORDER)));
+        assert!(!is_low_s(&LOW_S));
+    }
+
+    #[test]
+    fn verify_accepts_low_s_signature() {
+        let sig = signature(&LOW_S);
+        let data = secp_data(&[(&PUBKEY, &sig, MESSAGE)]);
+        assert!(verify(&data, sig).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_high_s_signature() {
+        let sig = signature(&HIGH_S);
+        let data = secp_data(&[(&PUBKEY, &sig, MESSAGE)]);
+        assert_eq!(
+            verify(&data, sig).unwrap_err(),
+            LazorKitError::HighSSignature.into()
+        );
+    }
+
+    #[test]
+    fn verify_finds_entry_among_multiple_signatures() {
+        let sig = signature(&LOW_S);
+        let other_pubkey = [2u8; 33];
+        let other_sig = [7u8; 64];
+        let data = secp_data(&[
+            (&other_pubkey, &other_sig, MESSAGE),
+            (&PUBKEY, &other_sig, b"other message"),
+            (&PUBKEY, &sig, MESSAGE),
+        ]);
+        assert!(verify(&data, sig).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_missing_entry() {
+        let sig = signature(&LOW_S);
+        let data = secp_data(&[(&PUBKEY, &sig, b"other message")]);
+        assert_eq!(
+            verify(&data, sig).unwrap_err(),
+            LazorKitError::VerifyDataMismatchError.into()
+        );
+        assert_eq!(
+            verify(&[0, 0], signature(&LOW_S)).unwrap_err(),
+            LazorKitError::VerifyHeaderMismatchError.into()
+        );
+    }
+
+    #[test]
+    fn verify_reads_components_from_other_instructions() {
+        let sig = signature(&LOW_S);
+        // Pubkey and message live in instruction 3, the signature inline
+        let other_ix = [vec![0u8; 5], PUBKEY.to_vec(), MESSAGE.to_vec()].concat();
+        let mut data = vec![1, 0];
+        for value in [
+            16,
+            SECP_CURRENT_INSTRUCTION,
+            5,
+            3,
+            5 + 33,
+            MESSAGE.len() as u16,
+            3,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&sig);
+
+        let load = |index: u16| match index {
+            3 => Ok(other_ix.clone()),
+            _ => no_instructions(index),
+        };
+        assert!(verify_secp256r1_data(&data, load, PUBKEY, MESSAGE.to_vec(), sig).is_ok());
+    }
+
+    fn amount_of(owner: Pubkey, mut data: Vec<u8>) -> Result<u64> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        token_account_amount(&account)
+    }
+
+    fn token_account_data(amount: u64, len: usize) -> Vec<u8> {
+        let mut data = vec![0; len];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn token_account_amount_reads_spl_token_accounts() {
+        let data = token_account_data(42, TOKEN_ACCOUNT_SIZE);
+        assert_eq!(amount_of(TOKEN_PROGRAM_ID, data).unwrap(), 42);
+    }
+
+    #[test]
+    fn token_account_amount_reads_token_2022_accounts_with_extensions() {
+        let mut data = token_account_data(7, TOKEN_ACCOUNT_SIZE + 8);
+        data[TOKEN_ACCOUNT_SIZE] = 2;
+        assert_eq!(amount_of(TOKEN_2022_PROGRAM_ID, data).unwrap(), 7);
+    }
+
+    #[test]
+    fn token_account_amount_rejects_other_accounts() {
+        let data = token_account_data(1, TOKEN_ACCOUNT_SIZE);
+        assert!(amount_of(Pubkey::new_unique(), data).is_err());
+
+        let mut mint = token_account_data(1, TOKEN_ACCOUNT_SIZE + 8);
+        mint[TOKEN_ACCOUNT_SIZE] = 1;
+        assert!(amount_of(TOKEN_2022_PROGRAM_ID, mint).is_err());
+        assert!(amount_of(TOKEN_PROGRAM_ID, vec![0; 82]).is_err());
+    }
+}
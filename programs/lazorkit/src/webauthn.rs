@@ -0,0 +1,390 @@
+use anchor_lang::{prelude::*, solana_program::hash::hash};
+
+use crate::{error::LazorKitError, state::RelyingParty};
+
+/// Authenticator data flag bits
+pub const FLAG_USER_PRESENT: u8 = 0x01;
+pub const FLAG_USER_VERIFIED: u8 = 0x04;
+pub const FLAG_BACKUP_ELIGIBLE: u8 = 0x08;
+pub const FLAG_BACKED_UP: u8 = 0x10;
+
+/// Size of the fixed authenticator data prefix: rpIdHash, flags and signCount
+const AUTHENTICATOR_DATA_MIN_SIZE: usize = 37;
+
+/// Client data type of an assertion produced by `navigator.credentials.get`
+const WEBAUTHN_GET_TYPE: &str = "webauthn.get";
+
+/// Deepest nesting of objects and arrays accepted in the clientDataJSON, bounding the
+/// recursion of [`JsonParser::skip_value`] on the program's small stack
+const MAX_JSON_DEPTH: usize = 8;
+
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Fixed fields of the WebAuthn authenticator data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticatorData {
+    pub rp_id_hash: [u8; 32],
+    pub flags: u8,
+    pub sign_count: u32,
+}
+
+impl AuthenticatorData {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= AUTHENTICATOR_DATA_MIN_SIZE,
+            LazorKitError::InvalidAuthenticatorData
+        );
+        Ok(Self {
+            rp_id_hash: data[..32].try_into().unwrap(),
+            flags: data[32],
+            sign_count: u32::from_be_bytes(data[33..37].try_into().unwrap()),
+        })
+    }
+
+    #[inline]
+    pub fn has_flags(&self, flags: u8) -> bool {
+        self.flags & flags == flags
+    }
+}
+
+/// Fields of the clientDataJSON that are checked on-chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientData<'a> {
+    pub type_: &'a str,
+    pub challenge: &'a str,
+    pub origin: &'a str,
+}
+
+impl<'a> ClientData<'a> {
+    pub fn parse(json: &'a [u8]) -> Result<Self> {
+        let json = core::str::from_utf8(json).map_err(|_| LazorKitError::InvalidClientData)?;
+        let (mut type_, mut challenge, mut origin) = (None, None, None);
+
+        let mut parser = JsonParser::new(json);
+        parser.expect(b'{')?;
+        if !parser.consume(b'}') {
+            loop {
+                let key = parser.string()?;
+                parser.expect(b':')?;
+                let slot = match key {
+                    "type" => Some(&mut type_),
+                    "challenge" => Some(&mut challenge),
+                    "origin" => Some(&mut origin),
+                    _ => None,
+                };
+                match slot {
+                    Some(slot) => {
+                        // Duplicate keys could be interpreted differently by the browser
+                        require!(slot.is_none(), LazorKitError::InvalidClientData);
+                        let value = parser.string()?;
+                        require!(!value.contains('\\'), LazorKitError::InvalidClientData);
+                        *slot = Some(value);
+                    }
+                    None => parser.skip_value(0)?,
+                }
+                if parser.consume(b'}') {
+                    break;
+                }
+                parser.expect(b',')?;
+            }
+        }
+        parser.skip_whitespace();
+        require!(parser.is_done(), LazorKitError::InvalidClientData);
+
+        Ok(Self {
+            type_: type_.ok_or(LazorKitError::InvalidClientData)?,
+            challenge: challenge.ok_or(LazorKitError::InvalidClientData)?,
+            origin: origin.ok_or(LazorKitError::InvalidClientData)?,
+        })
+    }
+}
+
+/// Verify a WebAuthn assertion over `challenge`, created for one of `relying_parties`,
+/// and return its authenticator data.
+///
+/// The secp256r1 signature itself is checked separately against [`signed_message`].
+pub fn verify_assertion(
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    challenge: &[u8; 32],
+    relying_parties: &[RelyingParty],
+) -> Result<AuthenticatorData> {
+    let client_data = ClientData::parse(client_data_json)?;
+    require!(
+        client_data.type_ == WEBAUTHN_GET_TYPE,
+        LazorKitError::InvalidClientData
+    );
+    require!(
+        client_data.challenge == base64url_encode(challenge),
+        LazorKitError::ChallengeMismatch
+    );
+
+    let auth_data = AuthenticatorData::parse(authenticator_data)?;
+    let mut origin_matches = relying_parties
+        .iter()
+        .filter(|rp| rp.origin == client_data.origin)
+        .peekable();
+    require!(
+        origin_matches.peek().is_some(),
+        LazorKitError::OriginNotAllowed
+    );
+    require!(
+        origin_matches.any(|rp| rp.rp_id_hash == auth_data.rp_id_hash),
+        LazorKitError::RpIdHashMismatch
+    );
+    require!(
+        auth_data.has_flags(FLAG_USER_PRESENT),
+        LazorKitError::UserNotPresent
+    );
+
+    Ok(auth_data)
+}
+
+/// Message signed by the authenticator: `authenticatorData || sha256(clientDataJSON)`
+pub fn signed_message(authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+    [authenticator_data, &hash(client_data_json).to_bytes()].concat()
+}
+
+/// Unpadded base64url encoding, as used for the clientDataJSON challenge
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | (*b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64URL_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Minimal JSON reader, sufficient to walk the top-level object of a clientDataJSON
+struct JsonParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn consume(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        require!(self.consume(byte), LazorKitError::InvalidClientData);
+        Ok(())
+    }
+
+    /// Read a string and return its raw contents, escape sequences left as-is
+    fn string(&mut self) -> Result<&'a str> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        loop {
+            match self.peek().ok_or(LazorKitError::InvalidClientData)? {
+                b'"' => break,
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+        let value = self
+            .input
+            .get(start..self.pos)
+            .ok_or(LazorKitError::InvalidClientData)?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    /// Skip a value nested in `depth` objects or arrays below the top-level object
+    fn skip_value(&mut self, depth: usize) -> Result<()> {
+        self.skip_whitespace();
+        match self.peek().ok_or(LazorKitError::InvalidClientData)? {
+            b'"' => {
+                self.string()?;
+            }
+            open @ (b'{' | b'[') => {
+                require!(depth < MAX_JSON_DEPTH, LazorKitError::InvalidClientData);
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                if !self.consume(close) {
+                    loop {
+                        if open == b'{' {
+                            self.string()?;
+                            self.expect(b':')?;
+                        }
+                        self.skip_value(depth + 1)?;
+                        if self.consume(close) {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b) if b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+                {
+                    self.pos += 1;
+                }
+                require!(self.pos > start, LazorKitError::InvalidClientData);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Result<ClientData<'_>> {
+        ClientData::parse(json.as_bytes())
+    }
+
+    fn invalid_client_data() -> Error {
+        LazorKitError::InvalidClientData.into()
+    }
+
+    #[test]
+    fn base64url_matches_known_vectors() {
+        // RFC 4648 test vectors, unpadded
+        for (input, encoded) in [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64url_encode(input.as_bytes()), encoded);
+        }
+        // The url-safe alphabet replaces `+` and `/`
+        assert_eq!(base64url_encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(
+            base64url_encode(&[0u8; 32]),
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+        );
+    }
+
+    #[test]
+    fn client_data_fields_are_read_in_any_order() {
+        let client_data = parse(
+            r#" { "origin" : "https://a.test", "type":"webauthn.get",
+                "challenge":"abc" } "#,
+        )
+        .unwrap();
+        assert_eq!(
+            client_data,
+            ClientData {
+                type_: "webauthn.get",
+                challenge: "abc",
+                origin: "https://a.test",
+            }
+        );
+    }
+
+    #[test]
+    fn escaped_strings_are_skipped_whole() {
+        let client_data = parse(
+            r#"{"type":"webauthn.get","other":"a\",\"challenge\":\"forged","challenge":"abc","origin":"o"}"#,
+        )
+        .unwrap();
+        assert_eq!(client_data.challenge, "abc");
+    }
+
+    #[test]
+    fn escapes_in_checked_fields_are_rejected() {
+        assert_eq!(
+            parse(r#"{"type":"webauthn.get","challenge":"a\u0062c","origin":"o"}"#),
+            Err(invalid_client_data())
+        );
+    }
+
+    #[test]
+    fn nested_objects_and_arrays_are_skipped() {
+        let client_data = parse(
+            r#"{"type":"webauthn.get","tokenBinding":{"status":"present","ids":[1,-2.5e3,{"challenge":"forged"},[],{}]},"challenge":"abc","crossOrigin":false,"origin":"o","extra":null}"#,
+        )
+        .unwrap();
+        assert_eq!(client_data.challenge, "abc");
+        assert_eq!(client_data.origin, "o");
+    }
+
+    #[test]
+    fn nesting_is_capped() {
+        let nested = |depth: usize| {
+            format!(
+                r#"{{"type":"webauthn.get","challenge":"abc","origin":"o","x":{}1{}}}"#,
+                "[".repeat(depth),
+                "]".repeat(depth)
+            )
+        };
+        assert!(parse(&nested(MAX_JSON_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&nested(MAX_JSON_DEPTH + 1)),
+            Err(invalid_client_data())
+        );
+        assert_eq!(parse(&nested(10_000)), Err(invalid_client_data()));
+    }
+
+    #[test]
+    fn duplicate_checked_keys_are_rejected() {
+        assert_eq!(
+            parse(r#"{"type":"webauthn.get","challenge":"abc","challenge":"abc","origin":"o"}"#),
+            Err(invalid_client_data())
+        );
+        assert!(
+            parse(r#"{"type":"webauthn.get","x":1,"x":2,"challenge":"abc","origin":"o"}"#).is_ok()
+        );
+    }
+
+    #[test]
+    fn checked_fields_must_be_strings() {
+        for json in [
+            r#"{"type":"webauthn.get","challenge":5,"origin":"o"}"#,
+            r#"{"type":"webauthn.get","challenge":["abc"],"origin":"o"}"#,
+            r#"{"type":"webauthn.get","challenge":null,"origin":"o"}"#,
+            r#"{"type":"webauthn.get","origin":"o"}"#,
+        ] {
+            assert_eq!(parse(json), Err(invalid_client_data()));
+        }
+    }
+
+    #[test]
+    fn malformed_json_is_rejected() {
+        for json in [
+            r#"{"type":"webauthn.get","challenge":"abc","origin":"o""#,
+            r#"{"type":"webauthn.get","challenge":"abc","origin":"o"} x"#,
+            r#"{"type":"webauthn.get","challenge":"abc","origin":"o","x":}"#,
+            r#"{"type":"webauthn.get","challenge":"abc","origin":"o","x":"\"}"#,
+        ] {
+            assert_eq!(parse(json), Err(invalid_client_data()));
+        }
+    }
+}
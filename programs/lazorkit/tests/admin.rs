@@ -0,0 +1,226 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::{system_program, Discriminator, InstructionData};
+use common::*;
+use lazorkit::{
+    error::LazorKitError,
+    events::ConfigUpdated,
+    instructions::ConfigUpdate,
+    state::{Config, RelyingParty, WhitelistRulePrograms},
+};
+
+fn config_key() -> Pubkey {
+    Pubkey::find_program_address(&[Config::PREFIX_SEED], &lazorkit::ID).0
+}
+
+fn whitelist_key() -> Pubkey {
+    Pubkey::find_program_address(&[WhitelistRulePrograms::PREFIX_SEED], &lazorkit::ID).0
+}
+
+/// Fund `key` so it can pay for the whitelist growing
+fn fund(wallet: &mut Wallet, key: Pubkey) {
+    wallet.ledger.set(
+        key,
+        AccountState {
+            lamports: 1_000_000_000,
+            ..Default::default()
+        },
+    );
+}
+
+fn propose_admin(wallet: &mut Wallet, signer: Pubkey, new_admin: Pubkey) -> ProgramResult {
+    let metas = [
+        AccountMeta::new_readonly(signer, true),
+        AccountMeta::new(config_key(), false),
+    ];
+    let data = lazorkit::instruction::ProposeAdmin { new_admin }.data();
+    wallet.ledger.process(&metas, &data)
+}
+
+fn accept_admin(wallet: &mut Wallet, signer: Pubkey) -> ProgramResult {
+    let metas = [
+        AccountMeta::new_readonly(signer, true),
+        AccountMeta::new(config_key(), false),
+    ];
+    let data = lazorkit::instruction::AcceptAdmin {}.data();
+    wallet.ledger.process(&metas, &data)
+}
+
+fn whitelist_metas(signer: Pubkey) -> [AccountMeta; 4] {
+    [
+        AccountMeta::new(signer, true),
+        AccountMeta::new_readonly(config_key(), false),
+        AccountMeta::new(whitelist_key(), false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]
+}
+
+fn whitelist(wallet: &Wallet) -> Vec<Pubkey> {
+    wallet
+        .ledger
+        .anchor_account::<WhitelistRulePrograms>(&whitelist_key())
+        .unwrap()
+        .list
+}
+
+#[test]
+fn only_the_admin_manages_the_whitelist() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let admin = wallet.protocol_config().admin;
+    let stranger = Pubkey::new_unique();
+    fund(&mut wallet, admin);
+    fund(&mut wallet, stranger);
+    let program_id = Pubkey::new_unique();
+    let upsert = lazorkit::instruction::UpsertWhitelistRulePrograms { program_id }.data();
+    let remove = lazorkit::instruction::RemoveWhitelistRuleProgram { program_id }.data();
+
+    assert_eq!(
+        wallet.ledger.process(&whitelist_metas(stranger), &upsert),
+        Err(program_error(LazorKitError::Unauthorized))
+    );
+    wallet
+        .ledger
+        .process(&whitelist_metas(admin), &upsert)
+        .unwrap();
+    assert!(whitelist(&wallet).contains(&program_id));
+
+    assert_eq!(
+        wallet.ledger.process(&whitelist_metas(stranger), &remove),
+        Err(program_error(LazorKitError::Unauthorized))
+    );
+    wallet
+        .ledger
+        .process(&whitelist_metas(admin), &remove)
+        .unwrap();
+    assert!(!whitelist(&wallet).contains(&program_id));
+}
+
+#[test]
+fn admin_handover_takes_a_proposal_and_its_acceptance() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let admin = wallet.protocol_config().admin;
+    let new_admin = Pubkey::new_unique();
+    let stranger = Pubkey::new_unique();
+
+    // Only the admin proposes
+    assert_eq!(
+        propose_admin(&mut wallet, stranger, stranger),
+        Err(program_error(LazorKitError::Unauthorized))
+    );
+    propose_admin(&mut wallet, admin, new_admin).unwrap();
+    let config = wallet.protocol_config();
+    assert_eq!(config.admin, admin);
+    assert_eq!(config.pending_admin, Some(new_admin));
+
+    // Only the proposed admin accepts
+    for signer in [stranger, admin] {
+        assert_eq!(
+            accept_admin(&mut wallet, signer),
+            Err(program_error(LazorKitError::Unauthorized))
+        );
+    }
+    accept_admin(&mut wallet, new_admin).unwrap();
+    let config = wallet.protocol_config();
+    assert_eq!(config.admin, new_admin);
+    assert_eq!(config.pending_admin, None);
+
+    // The previous admin is locked out, and the proposal cannot be accepted twice
+    assert_eq!(
+        propose_admin(&mut wallet, admin, admin),
+        Err(program_error(LazorKitError::Unauthorized))
+    );
+    assert_eq!(
+        accept_admin(&mut wallet, new_admin),
+        Err(program_error(LazorKitError::Unauthorized))
+    );
+}
+
+fn update_config(wallet: &mut Wallet, signer: Pubkey, update: ConfigUpdate) -> ProgramResult {
+    let metas = [
+        AccountMeta::new_readonly(signer, true),
+        AccountMeta::new(config_key(), false),
+        AccountMeta::new_readonly(whitelist_key(), false),
+    ];
+    let data = lazorkit::instruction::UpdateConfig { update }.data();
+    wallet.ledger.process(&metas, &data)
+}
+
+#[test]
+fn every_config_update_is_applied_and_announced() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let admin = wallet.protocol_config().admin;
+    let treasury = Pubkey::new_unique();
+    let relying_party = RelyingParty {
+        rp_id_hash: hash(b"wallet.lazorkit.test").to_bytes(),
+        origin: "https://wallet.lazorkit.test".to_string(),
+    };
+    take_events();
+
+    for update in [
+        ConfigUpdate::CreateSmartWalletFee(5_000),
+        ConfigUpdate::ExecuteFee(700),
+        ConfigUpdate::DefaultRuleProgram(TRANSFER_LIMIT_ID),
+        ConfigUpdate::Treasury(treasury),
+        ConfigUpdate::RelyingParties(vec![relying_party.clone()]),
+    ] {
+        update_config(&mut wallet, admin, update.clone()).unwrap();
+
+        let events = take_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(&events[0][..8], ConfigUpdated::DISCRIMINATOR);
+        assert_eq!(events[0][8..], update.try_to_vec().unwrap());
+    }
+
+    let config = wallet.protocol_config();
+    assert_eq!(config.create_smart_wallet_fee, 5_000);
+    assert_eq!(config.execute_fee, 700);
+    assert_eq!(config.default_rule_program, TRANSFER_LIMIT_ID);
+    assert_eq!(config.treasury, treasury);
+    assert_eq!(config.relying_parties, vec![relying_party]);
+}
+
+#[test]
+fn only_the_admin_updates_the_config() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    take_events();
+
+    let result = update_config(
+        &mut wallet,
+        Pubkey::new_unique(),
+        ConfigUpdate::ExecuteFee(700),
+    );
+
+    assert_eq!(result, Err(program_error(LazorKitError::Unauthorized)));
+    assert!(take_events().is_empty());
+    assert_eq!(wallet.protocol_config().execute_fee, 0);
+}
+
+#[test]
+fn config_updates_are_validated() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let admin = wallet.protocol_config().admin;
+    take_events();
+
+    for (update, error) in [
+        (
+            ConfigUpdate::DefaultRuleProgram(Pubkey::new_unique()),
+            LazorKitError::RuleProgramNotWhitelisted,
+        ),
+        (
+            ConfigUpdate::RelyingParties(vec![]),
+            LazorKitError::InvalidRelyingParties,
+        ),
+    ] {
+        assert_eq!(
+            update_config(&mut wallet, admin, update),
+            Err(program_error(error))
+        );
+    }
+    assert!(take_events().is_empty());
+    let config = wallet.protocol_config();
+    assert_eq!(config.default_rule_program, DEFAULT_RULE_ID);
+    assert_eq!(config.relying_parties, vec![wallet.relying_party.clone()]);
+}
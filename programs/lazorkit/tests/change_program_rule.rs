@@ -0,0 +1,403 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use common::*;
+use lazorkit::{
+    error::LazorKitError,
+    instructions::{Action, RuleAccounts},
+};
+use rule_interface::{CheckRuleArgs, RuleInstruction};
+
+/// Change the wallet's `old` rule to `new` with the given destroy and init instruction data
+fn change_rule_with(
+    wallet: &mut Wallet,
+    old: Pubkey,
+    new: Pubkey,
+    destroy: Vec<u8>,
+    init: Vec<u8>,
+) -> ProgramResult {
+    let authenticator = wallet.authenticator_key(&wallet.passkey).0;
+    let remaining = [
+        // Destroy: [SmartWallet, SmartWalletAuthenticator]
+        AccountMeta::new(wallet.smart_wallet, false),
+        AccountMeta::new_readonly(authenticator, false),
+        // InitRule: [Payer, SmartWallet, SmartWalletAuthenticator]
+        AccountMeta::new(wallet.payer, true),
+        AccountMeta::new(wallet.smart_wallet, false),
+        AccountMeta::new_readonly(authenticator, false),
+    ];
+    let mut args = wallet.args(Action::ChangeProgramRule);
+    args.rule_data = cpi_data(destroy, 0, 2);
+    args.cpi_data = Some(cpi_data(init, 2, 3));
+    wallet.execute(
+        args,
+        ExecuteAccounts {
+            authenticator_program: old,
+            cpi_program: new,
+        },
+        &remaining,
+    )
+}
+
+fn change_rule(wallet: &mut Wallet, old: Pubkey, new: Pubkey) -> ProgramResult {
+    change_rule_with(
+        wallet,
+        old,
+        new,
+        RuleInstruction::Destroy.data(&()),
+        RuleInstruction::InitRule.data(&()),
+    )
+}
+
+fn rule_calls() -> Vec<(Pubkey, Option<RuleInstruction>)> {
+    take_cpis()
+        .iter()
+        .map(|cpi| (cpi.program_id, RuleInstruction::parse(&cpi.data)))
+        .collect()
+}
+
+#[test]
+fn migrates_through_three_rules_destroying_before_each_init() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let authenticator = wallet.authenticator_key(&wallet.passkey).0;
+
+    for (old, new) in [
+        (DEFAULT_RULE_ID, TRANSFER_LIMIT_ID),
+        (TRANSFER_LIMIT_ID, THIRD_RULE_ID),
+        (THIRD_RULE_ID, DEFAULT_RULE_ID),
+    ] {
+        change_rule(&mut wallet, old, new).unwrap();
+
+        let cpis = take_cpis();
+        let calls = cpis
+            .iter()
+            .map(|cpi| (cpi.program_id, RuleInstruction::parse(&cpi.data)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            vec![
+                (old, Some(RuleInstruction::Destroy)),
+                (new, Some(RuleInstruction::InitRule)),
+            ]
+        );
+        // The old rule authorizes its destroy with the signing authenticator
+        assert!(cpis[0]
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == authenticator && meta.is_signer));
+        assert_eq!(wallet.config().rule_programs, vec![new]);
+    }
+}
+
+#[test]
+fn migration_keeps_the_position_of_the_replaced_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+
+    change_rule(&mut wallet, DEFAULT_RULE_ID, THIRD_RULE_ID).unwrap();
+
+    assert_eq!(
+        wallet.config().rule_programs,
+        vec![THIRD_RULE_ID, TRANSFER_LIMIT_ID]
+    );
+}
+
+#[test]
+fn migration_requires_the_rule_interface_instructions() {
+    for (destroy, init) in [
+        (
+            RuleInstruction::InitRule.data(&()),
+            RuleInstruction::InitRule.data(&()),
+        ),
+        (
+            RuleInstruction::Destroy.data(&()),
+            RuleInstruction::CheckRule.data(&()),
+        ),
+        (vec![], RuleInstruction::InitRule.data(&())),
+    ] {
+        let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+
+        let result = change_rule_with(
+            &mut wallet,
+            DEFAULT_RULE_ID,
+            TRANSFER_LIMIT_ID,
+            destroy,
+            init,
+        );
+
+        assert_eq!(
+            result,
+            Err(program_error(LazorKitError::InvalidRuleInstruction))
+        );
+        assert!(take_cpis().is_empty());
+        assert_eq!(wallet.config().rule_programs, vec![DEFAULT_RULE_ID]);
+    }
+}
+
+#[test]
+fn migration_rejects_attached_foreign_or_unlisted_rules() {
+    let unlisted = Pubkey::new_unique();
+    for (rules, old, new, error) in [
+        // The new rule is already attached
+        (
+            vec![DEFAULT_RULE_ID, TRANSFER_LIMIT_ID],
+            DEFAULT_RULE_ID,
+            TRANSFER_LIMIT_ID,
+            LazorKitError::RuleProgramAlreadyAttached,
+        ),
+        // The old rule is not one of the wallet's
+        (
+            vec![DEFAULT_RULE_ID],
+            TRANSFER_LIMIT_ID,
+            THIRD_RULE_ID,
+            LazorKitError::InvalidRuleProgram,
+        ),
+        // The new rule is not whitelisted
+        (
+            vec![DEFAULT_RULE_ID],
+            DEFAULT_RULE_ID,
+            unlisted,
+            LazorKitError::InvalidRuleProgram,
+        ),
+    ] {
+        let mut wallet = Wallet::new(&rules);
+        wallet.ledger.set_program(unlisted);
+
+        assert_eq!(
+            change_rule(&mut wallet, old, new),
+            Err(program_error(error))
+        );
+        assert!(take_cpis().is_empty());
+        assert_eq!(wallet.config().rule_programs, rules);
+    }
+}
+
+#[test]
+fn migration_is_aborted_when_the_old_rule_refuses_to_be_destroyed() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    fail_cpis_to(DEFAULT_RULE_ID);
+
+    let result = change_rule(&mut wallet, DEFAULT_RULE_ID, TRANSFER_LIMIT_ID);
+
+    assert_eq!(result, Err(ProgramError::Custom(1)));
+    // The init of the new rule never ran
+    let cpis = take_cpis();
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].program_id, DEFAULT_RULE_ID);
+    assert_eq!(wallet.config().rule_programs, vec![DEFAULT_RULE_ID]);
+}
+
+#[test]
+fn delisted_rule_is_still_destroyed_when_migrating_away() {
+    let mut wallet = Wallet::new(&[THIRD_RULE_ID]);
+    wallet.delist(THIRD_RULE_ID);
+
+    change_rule(&mut wallet, THIRD_RULE_ID, DEFAULT_RULE_ID).unwrap();
+
+    assert_eq!(
+        rule_calls(),
+        vec![
+            (THIRD_RULE_ID, Some(RuleInstruction::Destroy)),
+            (DEFAULT_RULE_ID, Some(RuleInstruction::InitRule)),
+        ]
+    );
+    assert_eq!(wallet.config().rule_programs, vec![DEFAULT_RULE_ID]);
+}
+
+#[test]
+fn delisted_rule_cannot_skip_its_destroy() {
+    let mut wallet = Wallet::new(&[THIRD_RULE_ID]);
+    wallet.delist(THIRD_RULE_ID);
+
+    let result = change_rule_with(
+        &mut wallet,
+        THIRD_RULE_ID,
+        DEFAULT_RULE_ID,
+        vec![],
+        RuleInstruction::InitRule.data(&()),
+    );
+    assert_eq!(
+        result,
+        Err(program_error(LazorKitError::InvalidRuleInstruction))
+    );
+
+    fail_cpis_to(THIRD_RULE_ID);
+    assert_eq!(
+        change_rule(&mut wallet, THIRD_RULE_ID, DEFAULT_RULE_ID),
+        Err(ProgramError::Custom(1))
+    );
+    assert_eq!(wallet.config().rule_programs, vec![THIRD_RULE_ID]);
+}
+
+/// Append the accounts of a `check_rule` for each of the wallet's rules to `remaining`
+fn check_every_rule(wallet: &Wallet, remaining: &mut Vec<AccountMeta>) -> Vec<RuleAccounts> {
+    wallet
+        .config()
+        .rule_programs
+        .into_iter()
+        .map(|program| {
+            let (rule, metas) = wallet.rule_check(program, remaining.len() as u8);
+            remaining.extend(metas);
+            rule
+        })
+        .collect()
+}
+
+fn add_rule(wallet: &mut Wallet, rule: Pubkey) -> ProgramResult {
+    let authenticator = wallet.authenticator_key(&wallet.passkey).0;
+    let mut remaining = vec![
+        // InitRule: [Payer, SmartWallet, SmartWalletAuthenticator]
+        AccountMeta::new(wallet.payer, true),
+        AccountMeta::new(wallet.smart_wallet, false),
+        AccountMeta::new_readonly(authenticator, false),
+    ];
+    let mut args = wallet.args(Action::AddRuleProgram);
+    args.cpi_data = Some(cpi_data(RuleInstruction::InitRule.data(&()), 0, 3));
+    args.rules = check_every_rule(wallet, &mut remaining);
+    wallet.execute(
+        args,
+        ExecuteAccounts {
+            authenticator_program: wallet.config().rule_programs[0],
+            cpi_program: rule,
+        },
+        &remaining,
+    )
+}
+
+#[test]
+fn adding_a_rule_needs_every_rule_to_approve() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+
+    add_rule(&mut wallet, THIRD_RULE_ID).unwrap();
+
+    let cpis = take_cpis();
+    let calls = cpis
+        .iter()
+        .map(|cpi| (cpi.program_id, RuleInstruction::parse(&cpi.data)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        calls,
+        vec![
+            (DEFAULT_RULE_ID, Some(RuleInstruction::CheckRule)),
+            (TRANSFER_LIMIT_ID, Some(RuleInstruction::CheckRule)),
+            (THIRD_RULE_ID, Some(RuleInstruction::InitRule)),
+        ]
+    );
+    // The rules see lazorkit writing the wallet config with the new rule
+    let check = CheckRuleArgs::try_from_instruction_data(&cpis[0].data).unwrap();
+    assert_eq!(check.program_id, lazorkit::ID);
+    assert_eq!(check.accounts[0].pubkey, wallet.smart_wallet_config);
+    let mut data = Action::AddRuleProgram.try_to_vec().unwrap();
+    data.extend_from_slice(THIRD_RULE_ID.as_ref());
+    assert_eq!(check.data, data);
+    assert_eq!(
+        wallet.config().rule_programs,
+        vec![DEFAULT_RULE_ID, TRANSFER_LIMIT_ID, THIRD_RULE_ID]
+    );
+}
+
+#[test]
+fn adding_a_rule_is_rejected_by_an_existing_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+    fail_cpis_to(TRANSFER_LIMIT_ID);
+
+    let result = add_rule(&mut wallet, THIRD_RULE_ID);
+
+    assert_eq!(result, Err(ProgramError::Custom(1)));
+    // The new rule was never initialized
+    assert!(rule_calls()
+        .iter()
+        .all(|(program, _)| *program != THIRD_RULE_ID));
+    assert_eq!(
+        wallet.config().rule_programs,
+        vec![DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]
+    );
+}
+
+#[test]
+fn adding_a_rule_requires_the_accounts_of_every_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+    let authenticator = wallet.authenticator_key(&wallet.passkey).0;
+    let mut remaining = vec![
+        AccountMeta::new(wallet.payer, true),
+        AccountMeta::new(wallet.smart_wallet, false),
+        AccountMeta::new_readonly(authenticator, false),
+    ];
+    let (rule, metas) = wallet.rule_check(DEFAULT_RULE_ID, 3);
+    remaining.extend(metas);
+    let mut args = wallet.args(Action::AddRuleProgram);
+    args.cpi_data = Some(cpi_data(RuleInstruction::InitRule.data(&()), 0, 3));
+    args.rules = vec![rule];
+
+    let result = wallet.execute(
+        args,
+        ExecuteAccounts {
+            authenticator_program: DEFAULT_RULE_ID,
+            cpi_program: THIRD_RULE_ID,
+        },
+        &remaining,
+    );
+
+    assert_eq!(
+        result,
+        Err(program_error(LazorKitError::InvalidAccountInput))
+    );
+    assert!(take_cpis().is_empty());
+}
+
+fn remove_rule(wallet: &mut Wallet, rule: Pubkey) -> ProgramResult {
+    let authenticator = wallet.authenticator_key(&wallet.passkey).0;
+    let mut remaining = vec![
+        // Destroy: [SmartWallet, SmartWalletAuthenticator]
+        AccountMeta::new(wallet.smart_wallet, false),
+        AccountMeta::new_readonly(authenticator, false),
+    ];
+    let mut args = wallet.args(Action::RemoveRuleProgram);
+    args.rule_data = cpi_data(RuleInstruction::Destroy.data(&()), 0, 2);
+    args.rules = check_every_rule(wallet, &mut remaining);
+    wallet.execute(
+        args,
+        ExecuteAccounts {
+            authenticator_program: rule,
+            cpi_program: anchor_lang::system_program::ID,
+        },
+        &remaining,
+    )
+}
+
+#[test]
+fn removing_a_delisted_rule_destroys_it() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID, THIRD_RULE_ID]);
+    wallet.delist(THIRD_RULE_ID);
+
+    remove_rule(&mut wallet, THIRD_RULE_ID).unwrap();
+
+    assert_eq!(
+        rule_calls(),
+        vec![
+            (DEFAULT_RULE_ID, Some(RuleInstruction::CheckRule)),
+            (THIRD_RULE_ID, Some(RuleInstruction::CheckRule)),
+            (THIRD_RULE_ID, Some(RuleInstruction::Destroy)),
+        ]
+    );
+    assert_eq!(wallet.config().rule_programs, vec![DEFAULT_RULE_ID]);
+}
+
+#[test]
+fn removing_a_rule_is_rejected_by_an_existing_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID, THIRD_RULE_ID]);
+    fail_cpis_to(DEFAULT_RULE_ID);
+
+    let result = remove_rule(&mut wallet, THIRD_RULE_ID);
+
+    assert_eq!(result, Err(ProgramError::Custom(1)));
+    // The removed rule was never destroyed
+    assert_eq!(
+        rule_calls(),
+        vec![(DEFAULT_RULE_ID, Some(RuleInstruction::CheckRule))]
+    );
+    assert_eq!(
+        wallet.config().rule_programs,
+        vec![DEFAULT_RULE_ID, THIRD_RULE_ID]
+    );
+}
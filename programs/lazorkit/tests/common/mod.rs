@@ -0,0 +1,745 @@
+//! In-memory runtime driving `lazorkit::entry` with a signed smart wallet.
+//!
+//! CPIs to other programs are recorded rather than executed, unless a stub stands in for
+//! the program. System program instructions are applied to the accounts, so Anchor's
+//! `init` and `close` behave as on-chain.
+
+#![allow(dead_code, deprecated)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    hash::hash,
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    sysvar::{
+        self,
+        instructions::{construct_instructions_data, BorrowedInstruction},
+    },
+};
+use anchor_lang::{system_program, InstructionData, Space};
+use lazorkit::{
+    constants::{SECP256R1_ID, SMART_WALLET_SEED},
+    instructions::{Action, CpiData, ExecuteInstructionArgs, RuleAccounts},
+    message::ExecuteMessage,
+    state::{
+        Config, RelyingParty, SmartWalletAuthenticator, SmartWalletConfig, VerificationPolicy,
+        WhitelistRulePrograms,
+    },
+    utils::PasskeyExt,
+    webauthn::{self, FLAG_USER_PRESENT, FLAG_USER_VERIFIED},
+    ID,
+};
+
+pub const DEFAULT_RULE_ID: Pubkey = pubkey!("B98ooLRYBP6m6Zsrd3Hnzn4UAejfVZwyDgMFaBNzVR2W");
+pub const TRANSFER_LIMIT_ID: Pubkey = pubkey!("HjgdxTNPqpL59KLRVDwQ28cqam2SxBirnNN5SFAFGHZ8");
+pub const THIRD_RULE_ID: Pubkey = pubkey!("RuLe111111111111111111111111111111111111111");
+
+pub const ORIGIN: &str = "https://app.lazorkit.test";
+pub const PASSKEY: [u8; 33] = [2; 33];
+pub const SECOND_PASSKEY: [u8; 33] = [3; 33];
+pub const TREASURY: Pubkey = pubkey!("Treasury11111111111111111111111111111111111");
+
+/// Low-S signature; the precompile itself does not run, lazorkit only matches its data
+const SIGNATURE: [u8; 64] = [1; 64];
+const SECP_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// A CPI lazorkit made to a program other than the system program
+#[derive(Clone, Debug)]
+pub struct Cpi {
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+    pub accounts: Vec<AccountMeta>,
+}
+
+/// Stand-in for a program lazorkit invokes, run after the call is recorded
+pub type ProgramStub = fn(&Instruction) -> ProgramResult;
+
+thread_local! {
+    static CPIS: RefCell<Vec<Cpi>> = const { RefCell::new(Vec::new()) };
+    static PROGRAM_STUBS: RefCell<HashMap<Pubkey, ProgramStub>> = RefCell::new(HashMap::new());
+    /// Stubbed programs being run, innermost last
+    static INVOKED: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+    static EVENTS: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+    static FAILING_PROGRAMS: RefCell<HashSet<Pubkey>> = RefCell::new(HashSet::new());
+    static UNIX_TIMESTAMP: Cell<i64> = const { Cell::new(1_700_000_000) };
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        RETURN_DATA.with(|data| *data.borrow_mut() = None);
+        if instruction.program_id == system_program::ID {
+            return apply_system_instruction(instruction, account_infos);
+        }
+        CPIS.with(|cpis| {
+            cpis.borrow_mut().push(Cpi {
+                program_id: instruction.program_id,
+                data: instruction.data.clone(),
+                accounts: instruction.accounts.clone(),
+            })
+        });
+        if FAILING_PROGRAMS.with(|failing| failing.borrow().contains(&instruction.program_id)) {
+            return Err(ProgramError::Custom(1));
+        }
+        let Some(stub) =
+            PROGRAM_STUBS.with(|stubs| stubs.borrow().get(&instruction.program_id).copied())
+        else {
+            return Ok(());
+        };
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.program_id));
+        let result = stub(instruction);
+        INVOKED.with(|invoked| invoked.borrow_mut().pop());
+        result
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: UNIX_TIMESTAMP.with(Cell::get),
+            ..Default::default()
+        };
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        0
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|data| data.borrow().clone())
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program_id = INVOKED.with(|invoked| invoked.borrow().last().copied().unwrap_or(ID));
+        RETURN_DATA
+            .with(|return_data| *return_data.borrow_mut() = Some((program_id, data.to_vec())));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        EVENTS.with(|events| events.borrow_mut().push(fields.concat()));
+    }
+}
+
+/// Apply the system program instructions Anchor issues to create and fund accounts
+fn apply_system_instruction(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+) -> ProgramResult {
+    let account = |index: usize| {
+        let key = instruction.accounts[index].pubkey;
+        account_infos
+            .iter()
+            .find(|info| *info.key == key)
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+    let data = &instruction.data;
+    let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let transfer = |from: &AccountInfo, to: &AccountInfo, lamports: u64| -> ProgramResult {
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        **to.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    };
+
+    match u32::from_le_bytes(data[0..4].try_into().unwrap()) {
+        // CreateAccount { lamports, space, owner }
+        0 => {
+            let to = account(1)?;
+            transfer(account(0)?, to, u64_at(4))?;
+            *to.try_borrow_mut_data()? = Box::leak(vec![0; u64_at(12) as usize].into_boxed_slice());
+            to.assign(&Pubkey::try_from(&data[20..52]).unwrap());
+        }
+        // Assign { owner }
+        1 => account(0)?.assign(&Pubkey::try_from(&data[4..36]).unwrap()),
+        // Transfer { lamports }
+        2 => transfer(account(0)?, account(1)?, u64_at(4))?,
+        // Allocate { space }
+        8 => {
+            *account(0)?.try_borrow_mut_data()? =
+                Box::leak(vec![0; u64_at(4) as usize].into_boxed_slice());
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+/// Run `stub` for every CPI made to `program` on this thread
+pub fn stub_program(program: Pubkey, stub: ProgramStub) {
+    PROGRAM_STUBS.with(|stubs| stubs.borrow_mut().insert(program, stub));
+}
+
+/// Make `program` fail every CPI made to it on this thread
+pub fn fail_cpis_to(program: Pubkey) {
+    FAILING_PROGRAMS.with(|failing| failing.borrow_mut().insert(program));
+}
+
+/// Set the unix timestamp of the clock sysvar on this thread
+pub fn set_unix_timestamp(timestamp: i64) {
+    UNIX_TIMESTAMP.with(|clock| clock.set(timestamp));
+}
+
+/// CPIs recorded on this thread since the last call
+pub fn take_cpis() -> Vec<Cpi> {
+    CPIS.with(|cpis| std::mem::take(&mut *cpis.borrow_mut()))
+}
+
+/// Events emitted on this thread since the last call, as `discriminator || data`
+pub fn take_events() -> Vec<Vec<u8>> {
+    EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+}
+
+/// Error lazorkit returns for `error`
+pub fn program_error(error: impl Into<anchor_lang::error::Error>) -> ProgramError {
+    error.into().into()
+}
+
+#[derive(Clone, Debug)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl Default for AccountState {
+    fn default() -> Self {
+        Self {
+            lamports: 0,
+            data: vec![],
+            owner: system_program::ID,
+            executable: false,
+        }
+    }
+}
+
+fn leak<T>(value: T) -> &'static mut T {
+    Box::leak(Box::new(value))
+}
+
+/// Account key preceded by the length of the account data, where `AccountInfo::resize`
+/// reads it in the serialized program input
+#[repr(C)]
+struct SerializedKey {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+/// Account data laid out as in the serialized program input: preceded by its length and
+/// followed by room to grow, so that `AccountInfo::resize` works as on-chain
+fn leak_data(data: &[u8]) -> &'static mut [u8] {
+    let words = (8 + data.len() + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+    let buffer = Box::leak(vec![0u64; words].into_boxed_slice());
+    buffer[0] = data.len() as u64;
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr().cast::<u8>(), words * 8) };
+    bytes[8..8 + data.len()].copy_from_slice(data);
+    &mut bytes[8..8 + data.len()]
+}
+
+/// Accounts by key; instructions are applied atomically, as in a transaction
+#[derive(Default)]
+pub struct Ledger {
+    pub accounts: BTreeMap<Pubkey, AccountState>,
+}
+
+impl Ledger {
+    pub fn set(&mut self, key: Pubkey, state: AccountState) {
+        self.accounts.insert(key, state);
+    }
+
+    /// Store an Anchor account, leaving room for its variable-length fields to grow
+    pub fn set_anchor_account<T: AccountSerialize>(
+        &mut self,
+        key: Pubkey,
+        account: &T,
+        space: usize,
+    ) {
+        let mut data = Vec::with_capacity(space);
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space.max(data.len()), 0);
+        self.set(
+            key,
+            AccountState {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: ID,
+                executable: false,
+            },
+        );
+    }
+
+    pub fn set_program(&mut self, key: Pubkey) {
+        self.set(
+            key,
+            AccountState {
+                lamports: 1,
+                executable: true,
+                ..Default::default()
+            },
+        );
+    }
+
+    pub fn anchor_account<T: AccountDeserialize>(&self, key: &Pubkey) -> Option<T> {
+        let state = self.accounts.get(key)?;
+        T::try_deserialize(&mut &state.data[..]).ok()
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |state| state.lamports)
+    }
+
+    /// Account infos for `metas`, duplicates sharing the same account
+    fn account_infos(&self, metas: &[AccountMeta]) -> &'static [AccountInfo<'static>] {
+        let mut infos: Vec<AccountInfo<'static>> = Vec::with_capacity(metas.len());
+        for meta in metas {
+            if let Some(info) = infos.iter().find(|info| *info.key == meta.pubkey) {
+                let info = info.clone();
+                infos.push(info);
+                continue;
+            }
+            let state = self.accounts.get(&meta.pubkey).cloned().unwrap_or_default();
+            let key = leak(SerializedKey {
+                original_data_len: state.data.len() as u32,
+                key: meta.pubkey,
+            });
+            infos.push(AccountInfo::new(
+                &key.key,
+                meta.is_signer,
+                meta.is_writable,
+                leak(state.lamports),
+                leak_data(&state.data),
+                leak(state.owner),
+                state.executable,
+                0,
+            ));
+        }
+        Box::leak(infos.into_boxed_slice())
+    }
+
+    /// Run a lazorkit instruction, keeping its account changes only if it succeeds
+    pub fn process(&mut self, metas: &[AccountMeta], data: &[u8]) -> ProgramResult {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let infos = self.account_infos(metas);
+        lazorkit::entry(&ID, infos, data)?;
+
+        for info in infos {
+            if info.lamports() == 0 {
+                self.accounts.remove(info.key);
+                continue;
+            }
+            self.set(
+                *info.key,
+                AccountState {
+                    lamports: info.lamports(),
+                    data: info.data.borrow().to_vec(),
+                    owner: *info.owner,
+                    executable: info.executable,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Accounts of `execute_instruction` that vary between actions
+#[derive(Clone, Copy)]
+pub struct ExecuteAccounts {
+    pub authenticator_program: Pubkey,
+    pub cpi_program: Pubkey,
+}
+
+/// A smart wallet with one passkey, its program accounts and a funded payer
+pub struct Wallet {
+    pub ledger: Ledger,
+    pub smart_wallet: Pubkey,
+    pub smart_wallet_config: Pubkey,
+    pub passkey: [u8; 33],
+    pub payer: Pubkey,
+    pub relying_party: RelyingParty,
+    /// Counter the passkey reports in its next assertion
+    pub sign_count: u32,
+    /// Whether the next assertion reports user verification
+    pub user_verified: bool,
+}
+
+impl Wallet {
+    /// Wallet governed by `rule_programs`, with every test rule program whitelisted
+    pub fn new(rule_programs: &[Pubkey]) -> Self {
+        let mut ledger = Ledger::default();
+        let relying_party = RelyingParty {
+            rp_id_hash: hash(b"app.lazorkit.test").to_bytes(),
+            origin: ORIGIN.to_string(),
+        };
+
+        let (config, authority_bump) = Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID);
+        ledger.set_anchor_account(
+            config,
+            &Config {
+                admin: Pubkey::new_unique(),
+                treasury: TREASURY,
+                default_rule_program: DEFAULT_RULE_ID,
+                authority_bump,
+                relying_parties: vec![relying_party.clone()],
+                ..Default::default()
+            },
+            8 + Config::INIT_SPACE,
+        );
+
+        let whitelist = [DEFAULT_RULE_ID, TRANSFER_LIMIT_ID, THIRD_RULE_ID];
+        let (whitelist_key, whitelist_bump) =
+            Pubkey::find_program_address(&[WhitelistRulePrograms::PREFIX_SEED], &ID);
+        ledger.set_anchor_account(
+            whitelist_key,
+            &WhitelistRulePrograms {
+                list: whitelist.to_vec(),
+                bump: whitelist_bump,
+            },
+            WhitelistRulePrograms::space(whitelist.len()),
+        );
+
+        let id = 7u64;
+        let (smart_wallet, wallet_bump) =
+            Pubkey::find_program_address(&[SMART_WALLET_SEED, &id.to_le_bytes()], &ID);
+        ledger.set(
+            smart_wallet,
+            AccountState {
+                lamports: 10_000_000_000,
+                owner: ID,
+                ..Default::default()
+            },
+        );
+        let (smart_wallet_config, _) = Pubkey::find_program_address(
+            &[SmartWalletConfig::PREFIX_SEED, smart_wallet.as_ref()],
+            &ID,
+        );
+        ledger.set_anchor_account(
+            smart_wallet_config,
+            &SmartWalletConfig {
+                version: SmartWalletConfig::VERSION,
+                id,
+                rule_programs: rule_programs.to_vec(),
+                authenticator_count: 1,
+                verification_policy: VerificationPolicy::default(),
+                bump: wallet_bump,
+                ..Default::default()
+            },
+            8 + SmartWalletConfig::INIT_SPACE,
+        );
+
+        let payer = Pubkey::new_unique();
+        ledger.set(
+            payer,
+            AccountState {
+                lamports: 1_000_000_000,
+                ..Default::default()
+            },
+        );
+        ledger.set(TREASURY, AccountState::default());
+        ledger.set_program(ID);
+        ledger.set_program(system_program::ID);
+        for program in whitelist {
+            ledger.set_program(program);
+        }
+
+        let mut wallet = Self {
+            ledger,
+            smart_wallet,
+            smart_wallet_config,
+            passkey: PASSKEY,
+            payer,
+            relying_party,
+            sign_count: 1,
+            user_verified: true,
+        };
+        wallet.add_authenticator(PASSKEY);
+        wallet
+    }
+
+    pub fn authenticator_key(&self, passkey: &[u8; 33]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[passkey.to_hashed_bytes(self.smart_wallet).as_ref()], &ID)
+    }
+
+    /// Store an authenticator for `passkey` without going through lazorkit
+    pub fn add_authenticator(&mut self, passkey: [u8; 33]) {
+        let (key, bump) = self.authenticator_key(&passkey);
+        let authenticator = SmartWalletAuthenticator {
+            passkey_pubkey: passkey,
+            smart_wallet: self.smart_wallet,
+            sign_count: 0,
+            clone_suspected: false,
+            bump,
+        };
+        self.ledger.set_anchor_account(
+            key,
+            &authenticator,
+            8 + SmartWalletAuthenticator::INIT_SPACE,
+        );
+    }
+
+    pub fn config(&self) -> SmartWalletConfig {
+        self.ledger
+            .anchor_account(&self.smart_wallet_config)
+            .expect("smart wallet config")
+    }
+
+    pub fn set_config(&mut self, update: impl FnOnce(&mut SmartWalletConfig)) {
+        let mut config = self.config();
+        update(&mut config);
+        self.ledger.set_anchor_account(
+            self.smart_wallet_config,
+            &config,
+            8 + SmartWalletConfig::INIT_SPACE,
+        );
+    }
+
+    /// Lazorkit's protocol config
+    pub fn protocol_config(&self) -> Config {
+        self.ledger
+            .anchor_account(&Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID).0)
+            .expect("protocol config")
+    }
+
+    pub fn set_protocol_config(&mut self, update: impl FnOnce(&mut Config)) {
+        let mut config = self.protocol_config();
+        update(&mut config);
+        self.ledger.set_anchor_account(
+            Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID).0,
+            &config,
+            8 + Config::INIT_SPACE,
+        );
+    }
+
+    /// Remove `program` from the whitelist, as `remove_whitelist_rule_program` would
+    pub fn delist(&mut self, program: Pubkey) {
+        let key = Pubkey::find_program_address(&[WhitelistRulePrograms::PREFIX_SEED], &ID).0;
+        let mut whitelist: WhitelistRulePrograms = self.ledger.anchor_account(&key).unwrap();
+        whitelist.list.retain(|listed| *listed != program);
+        let space = WhitelistRulePrograms::space(whitelist.list.len());
+        self.ledger.set_anchor_account(key, &whitelist, space);
+    }
+
+    /// Remaining accounts of a `check_rule` call to `program` starting at `start_index`:
+    /// the program, the signing authenticator and one rule account
+    pub fn rule_check(&self, program: Pubkey, start_index: u8) -> (RuleAccounts, Vec<AccountMeta>) {
+        let metas = vec![
+            AccountMeta::new_readonly(program, false),
+            AccountMeta::new_readonly(self.authenticator_key(&self.passkey).0, false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+        ];
+        (rule_accounts(start_index, start_index + 1, 2), metas)
+    }
+
+    pub fn authenticator(&self, passkey: &[u8; 33]) -> Option<SmartWalletAuthenticator> {
+        self.ledger
+            .anchor_account(&self.authenticator_key(passkey).0)
+    }
+
+    /// Arguments for `action`, signed by the wallet's passkey when executed
+    pub fn args(&self, action: Action) -> ExecuteInstructionArgs {
+        ExecuteInstructionArgs {
+            passkey_pubkey: self.passkey,
+            signature: vec![],
+            authenticator_data: vec![],
+            client_data_json: vec![],
+            verify_instruction_index: 0,
+            rule_data: cpi_data(vec![], 0, 0),
+            rules: vec![],
+            cpi_data: None,
+            batch: vec![],
+            action,
+            create_new_authenticator: None,
+            revoke_authenticator: None,
+            verification_policy: None,
+            relying_parties: None,
+            nonce: self.config().nonce,
+            expiry: None,
+        }
+    }
+
+    /// Sign `args` with the wallet's passkey and run `execute_instruction`
+    pub fn execute(
+        &mut self,
+        args: ExecuteInstructionArgs,
+        accounts: ExecuteAccounts,
+        remaining: &[AccountMeta],
+    ) -> ProgramResult {
+        self.execute_with_remaining(args, accounts, remaining, remaining)
+    }
+
+    /// Sign `args` over the `signed` remaining accounts, then run `execute_instruction`
+    /// with `sent` in their place
+    pub fn execute_with_remaining(
+        &mut self,
+        args: ExecuteInstructionArgs,
+        accounts: ExecuteAccounts,
+        signed: &[AccountMeta],
+        sent: &[AccountMeta],
+    ) -> ProgramResult {
+        self.sign_and_execute(args, accounts, signed, sent, |_| {})
+    }
+
+    /// Sign `args`, then run `execute_instruction` with `alter` applied to them
+    pub fn execute_altered(
+        &mut self,
+        args: ExecuteInstructionArgs,
+        accounts: ExecuteAccounts,
+        remaining: &[AccountMeta],
+        alter: impl FnOnce(&mut ExecuteInstructionArgs),
+    ) -> ProgramResult {
+        self.sign_and_execute(args, accounts, remaining, remaining, alter)
+    }
+
+    fn sign_and_execute(
+        &mut self,
+        mut args: ExecuteInstructionArgs,
+        accounts: ExecuteAccounts,
+        signed: &[AccountMeta],
+        sent: &[AccountMeta],
+        alter: impl FnOnce(&mut ExecuteInstructionArgs),
+    ) -> ProgramResult {
+        let optional = |passkey: Option<[u8; 33]>| match passkey {
+            Some(passkey) => AccountMeta::new(self.authenticator_key(&passkey).0, false),
+            None => AccountMeta::new_readonly(ID, false),
+        };
+        let mut metas = vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID).0,
+                false,
+            ),
+            AccountMeta::new(TREASURY, false),
+            AccountMeta::new(self.smart_wallet, false),
+            AccountMeta::new(self.smart_wallet_config, false),
+            AccountMeta::new(self.authenticator_key(&args.passkey_pubkey).0, false),
+            AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[WhitelistRulePrograms::PREFIX_SEED], &ID).0,
+                false,
+            ),
+            AccountMeta::new_readonly(accounts.authenticator_program, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(accounts.cpi_program, false),
+            optional(args.create_new_authenticator),
+            optional(args.revoke_authenticator),
+        ];
+        let fixed = metas.len();
+        metas.extend_from_slice(signed);
+
+        // --- Duplicate accounts share their flags, so the message sees the merged metas ---
+        let infos = self.ledger.account_infos(&metas);
+        let message = ExecuteMessage::new(
+            &args,
+            self.smart_wallet,
+            accounts.authenticator_program,
+            accounts.cpi_program,
+            &infos[fixed..],
+        )
+        .map_err(ProgramError::from)?;
+        metas.truncate(fixed);
+        metas.extend_from_slice(sent);
+        let challenge = hash(&message.to_bytes().map_err(ProgramError::from)?).to_bytes();
+
+        let flags = FLAG_USER_PRESENT
+            | if self.user_verified {
+                FLAG_USER_VERIFIED
+            } else {
+                0
+            };
+        args.authenticator_data = [
+            self.relying_party.rp_id_hash.as_slice(),
+            &[flags],
+            &self.sign_count.to_be_bytes(),
+        ]
+        .concat();
+        args.client_data_json = format!(
+            r#"{{"type":"webauthn.get","challenge":"{}","origin":"{}"}}"#,
+            webauthn::base64url_encode(&challenge),
+            self.relying_party.origin,
+        )
+        .into_bytes();
+        args.signature = SIGNATURE.to_vec();
+        self.sign_count += 1;
+
+        let secp_data = secp_data(
+            &args.passkey_pubkey,
+            &SIGNATURE,
+            &webauthn::signed_message(&args.authenticator_data, &args.client_data_json),
+        );
+        self.ledger.set(
+            sysvar::instructions::ID,
+            AccountState {
+                lamports: 1,
+                data: construct_instructions_data(&[BorrowedInstruction {
+                    program_id: &SECP256R1_ID,
+                    accounts: vec![],
+                    data: &secp_data,
+                }]),
+                owner: sysvar::ID,
+                executable: false,
+            },
+        );
+
+        alter(&mut args);
+        let data = lazorkit::instruction::ExecuteInstruction { args }.data();
+        self.ledger.process(&metas, &data)
+    }
+}
+
+pub fn cpi_data(data: Vec<u8>, start_index: u8, length: u8) -> CpiData {
+    CpiData {
+        data,
+        start_index,
+        length,
+    }
+}
+
+pub fn rule_accounts(program_index: u8, start_index: u8, length: u8) -> RuleAccounts {
+    RuleAccounts {
+        program_index,
+        start_index,
+        length,
+    }
+}
+
+/// Secp256r1 precompile data checking a single signature held in the instruction itself
+fn secp_data(pubkey: &[u8], signature: &[u8], message: &[u8]) -> Vec<u8> {
+    const PAYLOAD_START: usize = 2 + 14;
+    let pubkey_offset = PAYLOAD_START as u16;
+    let signature_offset = pubkey_offset + pubkey.len() as u16;
+    let message_offset = signature_offset + signature.len() as u16;
+
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        SECP_CURRENT_INSTRUCTION,
+        pubkey_offset,
+        SECP_CURRENT_INSTRUCTION,
+        message_offset,
+        message.len() as u16,
+        SECP_CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    [data.as_slice(), pubkey, signature, message].concat()
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{system_program, InstructionData, Space};
+use common::*;
+use lazorkit::{
+    constants::SMART_WALLET_SEED,
+    state::{
+        Config, SmartWalletAuthenticator, SmartWalletConfig, SmartWalletSeq, WhitelistRulePrograms,
+    },
+    utils::PasskeyExt,
+    ID,
+};
+
+const NEW_PASSKEY: [u8; 33] = [4; 33];
+
+/// Create the smart wallet with sequence number `seq` for `NEW_PASSKEY`, returning its key
+fn create_smart_wallet(wallet: &mut Wallet, seq: u64) -> (Pubkey, ProgramResult) {
+    let (seq_key, seq_bump) = Pubkey::find_program_address(&[SmartWalletSeq::PREFIX_SEED], &ID);
+    wallet.ledger.set_anchor_account(
+        seq_key,
+        &SmartWalletSeq {
+            seq,
+            bump: seq_bump,
+        },
+        8 + SmartWalletSeq::INIT_SPACE,
+    );
+    let smart_wallet =
+        Pubkey::find_program_address(&[SMART_WALLET_SEED, &seq.to_le_bytes()], &ID).0;
+    let metas = [
+        AccountMeta::new(wallet.payer, true),
+        AccountMeta::new(seq_key, false),
+        AccountMeta::new_readonly(
+            Pubkey::find_program_address(&[WhitelistRulePrograms::PREFIX_SEED], &ID).0,
+            false,
+        ),
+        AccountMeta::new(smart_wallet, false),
+        AccountMeta::new(
+            Pubkey::find_program_address(
+                &[SmartWalletConfig::PREFIX_SEED, smart_wallet.as_ref()],
+                &ID,
+            )
+            .0,
+            false,
+        ),
+        AccountMeta::new(
+            Pubkey::find_program_address(
+                &[NEW_PASSKEY.to_hashed_bytes(smart_wallet).as_ref()],
+                &ID,
+            )
+            .0,
+            false,
+        ),
+        AccountMeta::new_readonly(
+            Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID).0,
+            false,
+        ),
+        AccountMeta::new(TREASURY, false),
+        AccountMeta::new_readonly(DEFAULT_RULE_ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    let data = lazorkit::instruction::CreateSmartWallet {
+        passkey_pubkey: NEW_PASSKEY,
+        rule_data: vec![9],
+        relying_parties: None,
+    }
+    .data();
+    (smart_wallet, wallet.ledger.process(&metas, &data))
+}
+
+#[test]
+fn creation_fee_is_paid_by_the_signer() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    wallet.set_protocol_config(|config| config.create_smart_wallet_fee = 5_000);
+    let payer_before = wallet.ledger.lamports(&wallet.payer);
+
+    let (smart_wallet, result) = create_smart_wallet(&mut wallet, 8);
+    result.unwrap();
+
+    let rent = Rent::default();
+    assert_eq!(wallet.ledger.lamports(&TREASURY), 5_000);
+    assert_eq!(
+        wallet.ledger.lamports(&smart_wallet),
+        rent.minimum_balance(0)
+    );
+    assert_eq!(
+        payer_before - wallet.ledger.lamports(&wallet.payer),
+        5_000
+            + rent.minimum_balance(0)
+            + rent.minimum_balance(8 + SmartWalletConfig::INIT_SPACE)
+            + rent.minimum_balance(8 + SmartWalletAuthenticator::INIT_SPACE)
+    );
+    let cpis = take_cpis();
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].program_id, DEFAULT_RULE_ID);
+}
+
+#[test]
+fn creation_fails_when_the_signer_cannot_pay_the_fee() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let payer_balance = wallet.ledger.lamports(&wallet.payer);
+    wallet.set_protocol_config(|config| config.create_smart_wallet_fee = payer_balance);
+
+    let (smart_wallet, result) = create_smart_wallet(&mut wallet, 8);
+
+    assert_eq!(result, Err(ProgramError::InsufficientFunds));
+    assert_eq!(wallet.ledger.lamports(&smart_wallet), 0);
+    assert_eq!(wallet.ledger.lamports(&TREASURY), 0);
+}
@@ -0,0 +1,714 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use common::*;
+use lazorkit::{
+    error::LazorKitError,
+    events::SignCountRegression,
+    instructions::{Action, BatchCpiData, ExecuteInstructionArgs, Expiry},
+    state::{RelyingParty, VerificationPolicy},
+};
+use rule_interface::{CheckRuleArgs, PostCheckArgs, PostCheckRequest, RuleInstruction};
+
+fn call_rule_program(
+    wallet: &Wallet,
+    rule_data: Vec<u8>,
+) -> (
+    lazorkit::instructions::ExecuteInstructionArgs,
+    Vec<AccountMeta>,
+) {
+    let mut args = wallet.args(Action::CallRuleProgram);
+    args.rule_data = cpi_data(rule_data, 0, 2);
+    let remaining = vec![
+        AccountMeta::new_readonly(wallet.authenticator_key(&wallet.passkey).0, false),
+        AccountMeta::new(Pubkey::new_unique(), false),
+    ];
+    (args, remaining)
+}
+
+const DEFAULT_RULE_ACCOUNTS: ExecuteAccounts = ExecuteAccounts {
+    authenticator_program: DEFAULT_RULE_ID,
+    cpi_program: system_program::ID,
+};
+
+#[test]
+fn call_rule_program_without_new_authenticator_only_calls_the_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (args, remaining) = call_rule_program(&wallet, vec![9, 9]);
+
+    wallet
+        .execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+        .unwrap();
+
+    let cpis = take_cpis();
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].program_id, DEFAULT_RULE_ID);
+    assert_eq!(cpis[0].data, vec![9, 9]);
+    assert_eq!(cpis[0].accounts[0].pubkey, remaining[0].pubkey);
+    assert!(cpis[0].accounts[0].is_signer);
+    assert_eq!(wallet.config().authenticator_count, 1);
+    assert_eq!(wallet.config().nonce, 1);
+}
+
+#[test]
+fn call_rule_program_creates_the_requested_authenticator() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (mut args, remaining) = call_rule_program(&wallet, vec![9, 9]);
+    args.create_new_authenticator = Some(SECOND_PASSKEY);
+
+    wallet
+        .execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+        .unwrap();
+
+    let authenticator = wallet.authenticator(&SECOND_PASSKEY).unwrap();
+    assert_eq!(authenticator.smart_wallet, wallet.smart_wallet);
+    assert_eq!(authenticator.sign_count, 0);
+    assert_eq!(wallet.config().authenticator_count, 2);
+    assert_eq!(take_cpis().len(), 1);
+}
+
+#[test]
+fn call_rule_program_rejects_rule_interface_instructions() {
+    for instruction in RuleInstruction::ALL {
+        let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+        let (args, remaining) = call_rule_program(&wallet, instruction.data(&()));
+
+        let result = wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &remaining);
+
+        assert_eq!(
+            result,
+            Err(program_error(LazorKitError::InvalidRuleInstruction))
+        );
+        assert!(take_cpis().is_empty());
+    }
+}
+
+fn policy(uv_required_actions: u16, execute_cpi_uv_threshold: u64) -> VerificationPolicy {
+    VerificationPolicy {
+        uv_required_actions,
+        execute_cpi_uv_threshold,
+    }
+}
+
+#[test]
+fn update_verification_policy_needs_every_rule_to_approve() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (rule, remaining) = wallet.rule_check(DEFAULT_RULE_ID, 0);
+    let mut args = wallet.args(Action::UpdateVerificationPolicy);
+    args.rules = vec![rule];
+    args.verification_policy = Some(policy(0, 5_000));
+
+    wallet
+        .execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+        .unwrap();
+
+    let cpis = take_cpis();
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(cpis[0].program_id, DEFAULT_RULE_ID);
+    let check = CheckRuleArgs::try_from_instruction_data(&cpis[0].data).unwrap();
+    assert_eq!(check.program_id, lazorkit::ID);
+    assert_eq!(check.accounts[0].pubkey, wallet.smart_wallet_config);
+    assert_eq!(wallet.config().verification_policy, policy(0, 5_000));
+}
+
+#[test]
+fn update_verification_policy_is_rejected_by_a_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (rule, remaining) = wallet.rule_check(DEFAULT_RULE_ID, 0);
+    let mut args = wallet.args(Action::UpdateVerificationPolicy);
+    args.rules = vec![rule];
+    args.verification_policy = Some(policy(0, 5_000));
+    fail_cpis_to(DEFAULT_RULE_ID);
+
+    let result = wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &remaining);
+
+    assert_eq!(result, Err(ProgramError::Custom(1)));
+    assert_eq!(
+        wallet.config().verification_policy,
+        VerificationPolicy::default()
+    );
+}
+
+#[test]
+fn update_verification_policy_requires_the_rule_accounts() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let mut args = wallet.args(Action::UpdateVerificationPolicy);
+    args.verification_policy = Some(policy(0, 5_000));
+
+    let result = wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &[]);
+
+    assert_eq!(
+        result,
+        Err(program_error(LazorKitError::InvalidAccountInput))
+    );
+    assert!(take_cpis().is_empty());
+}
+
+fn relying_party(rp_id: &str) -> RelyingParty {
+    RelyingParty {
+        rp_id_hash: hash(rp_id.as_bytes()).to_bytes(),
+        origin: ORIGIN.to_string(),
+    }
+}
+
+/// `UpdateRelyingParties` to `relying_parties`, approved by the default rule
+fn update_relying_parties(
+    wallet: &mut Wallet,
+    relying_parties: Option<Vec<RelyingParty>>,
+) -> ProgramResult {
+    let (rule, remaining) = wallet.rule_check(DEFAULT_RULE_ID, 0);
+    let mut args = wallet.args(Action::UpdateRelyingParties);
+    args.rules = vec![rule];
+    args.relying_parties = relying_parties;
+    wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+}
+
+fn check_authenticator(wallet: &mut Wallet) -> ProgramResult {
+    let args = wallet.args(Action::CheckAuthenticator);
+    wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &[])
+}
+
+#[test]
+fn updated_relying_parties_decide_the_accepted_rp_id_hash() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let global = wallet.relying_party.clone();
+    let wallet_rp = relying_party("wallet.lazorkit.test");
+
+    update_relying_parties(&mut wallet, Some(vec![wallet_rp.clone()])).unwrap();
+    let check = CheckRuleArgs::try_from_instruction_data(&take_cpis()[0].data).unwrap();
+    assert_eq!(check.program_id, lazorkit::ID);
+    assert_eq!(
+        wallet.config().relying_parties,
+        Some(vec![wallet_rp.clone()])
+    );
+
+    // Signed for the global relying party, whose rpIdHash the wallet no longer accepts
+    assert_eq!(
+        check_authenticator(&mut wallet),
+        Err(program_error(LazorKitError::RpIdHashMismatch))
+    );
+    wallet.relying_party = wallet_rp;
+    check_authenticator(&mut wallet).unwrap();
+
+    // Clearing the override falls back to the global relying parties
+    update_relying_parties(&mut wallet, None).unwrap();
+    assert_eq!(wallet.config().relying_parties, None);
+    assert_eq!(
+        check_authenticator(&mut wallet),
+        Err(program_error(LazorKitError::RpIdHashMismatch))
+    );
+    wallet.relying_party = global;
+    check_authenticator(&mut wallet).unwrap();
+}
+
+#[test]
+fn relying_parties_update_is_rejected_by_a_rule() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    fail_cpis_to(DEFAULT_RULE_ID);
+
+    let result = update_relying_parties(
+        &mut wallet,
+        Some(vec![relying_party("wallet.lazorkit.test")]),
+    );
+
+    assert_eq!(result, Err(ProgramError::Custom(1)));
+    assert_eq!(wallet.config().relying_parties, None);
+}
+
+#[test]
+fn relying_parties_update_requires_a_valid_set() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let too_many = (0..5)
+        .map(|i| relying_party(&format!("rp{i}.lazorkit.test")))
+        .collect();
+
+    for relying_parties in [vec![], too_many] {
+        assert_eq!(
+            update_relying_parties(&mut wallet, Some(relying_parties)),
+            Err(program_error(LazorKitError::InvalidRelyingParties))
+        );
+    }
+    assert!(take_cpis().is_empty());
+}
+
+/// `ExecuteCpi` of `data` to `program` with no accounts, checked by the default rule
+fn execute_cpi(
+    wallet: &mut Wallet,
+    program: Pubkey,
+    data: Vec<u8>,
+    accounts: Vec<AccountMeta>,
+) -> ProgramResult {
+    let (rule, mut remaining) = wallet.rule_check(DEFAULT_RULE_ID, 0);
+    let mut args = wallet.args(Action::ExecuteCpi);
+    args.rules = vec![rule];
+    args.cpi_data = Some(cpi_data(data, remaining.len() as u8, accounts.len() as u8));
+    remaining.extend(accounts);
+    wallet.execute(
+        args,
+        ExecuteAccounts {
+            authenticator_program: DEFAULT_RULE_ID,
+            cpi_program: program,
+        },
+        &remaining,
+    )
+}
+
+fn sol_transfer(wallet: &Wallet, lamports: u64) -> (Vec<u8>, Vec<AccountMeta>) {
+    let data = [[2, 0, 0, 0].as_slice(), &lamports.to_le_bytes()].concat();
+    let accounts = vec![
+        AccountMeta::new(wallet.smart_wallet, false),
+        AccountMeta::new(Pubkey::new_unique(), false),
+    ];
+    (data, accounts)
+}
+
+#[test]
+fn sol_transfers_within_the_threshold_need_only_user_presence() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    wallet.set_config(|config| config.verification_policy = policy(0, 1_000_000));
+    wallet.user_verified = false;
+
+    let (data, accounts) = sol_transfer(&wallet, 1_000_000);
+    execute_cpi(&mut wallet, system_program::ID, data, accounts.clone()).unwrap();
+    assert_eq!(wallet.ledger.lamports(&accounts[1].pubkey), 1_000_000);
+
+    let (data, accounts) = sol_transfer(&wallet, 1_000_001);
+    assert_eq!(
+        execute_cpi(&mut wallet, system_program::ID, data, accounts),
+        Err(program_error(LazorKitError::UserNotVerified))
+    );
+}
+
+#[test]
+fn sol_transfers_must_be_sent_from_the_smart_wallet() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (data, mut accounts) = sol_transfer(&wallet, 1_000);
+    accounts[0].pubkey = wallet.payer;
+
+    assert_eq!(
+        execute_cpi(&mut wallet, system_program::ID, data, accounts.clone()),
+        Err(program_error(LazorKitError::InvalidAccountInput))
+    );
+    assert_eq!(wallet.ledger.lamports(&accounts[1].pubkey), 0);
+}
+
+/// Rule approving every CPI, then rejecting any that took over 1_000_000 lamports out of
+/// the wallet once it has run
+fn capped_outflow_rule(instruction: &Instruction) -> ProgramResult {
+    match RuleInstruction::parse(&instruction.data) {
+        Some(RuleInstruction::CheckRule) => PostCheckRequest::default().set_return_data(),
+        Some(RuleInstruction::PostCheck) => {
+            let args = PostCheckArgs::try_from_slice(&instruction.data[8..]).unwrap();
+            if args.lamports.outflow() > 1_000_000 {
+                return Err(ProgramError::Custom(2));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[test]
+fn post_check_rejects_an_outflow_over_the_rule_limit() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    stub_program(DEFAULT_RULE_ID, capped_outflow_rule);
+
+    let (data, accounts) = sol_transfer(&wallet, 1_000_000);
+    execute_cpi(&mut wallet, system_program::ID, data, accounts.clone()).unwrap();
+
+    assert_eq!(wallet.ledger.lamports(&accounts[1].pubkey), 1_000_000);
+    let cpis = take_cpis();
+    let calls = cpis
+        .iter()
+        .map(|cpi| (cpi.program_id, RuleInstruction::parse(&cpi.data)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        calls,
+        vec![
+            (DEFAULT_RULE_ID, Some(RuleInstruction::CheckRule)),
+            (DEFAULT_RULE_ID, Some(RuleInstruction::PostCheck)),
+        ]
+    );
+    let post_check = PostCheckArgs::try_from_slice(&cpis[1].data[8..]).unwrap();
+    assert_eq!(post_check.lamports.outflow(), 1_000_000);
+    assert!(post_check.token_accounts.is_empty());
+
+    let (data, accounts) = sol_transfer(&wallet, 1_000_001);
+    assert_eq!(
+        execute_cpi(&mut wallet, system_program::ID, data, accounts.clone()),
+        Err(ProgramError::Custom(2))
+    );
+    assert_eq!(wallet.ledger.lamports(&accounts[1].pubkey), 0);
+}
+
+/// `ExecuteBatch` of a CPI with `data` to each program of `calls`, with no accounts,
+/// each checked by the default rule
+fn batch(
+    wallet: &Wallet,
+    calls: &[(Pubkey, Vec<u8>)],
+) -> (ExecuteInstructionArgs, Vec<AccountMeta>) {
+    let mut args = wallet.args(Action::ExecuteBatch);
+    let mut remaining = vec![];
+    for (program, data) in calls {
+        let program_index = remaining.len() as u8;
+        remaining.push(AccountMeta::new_readonly(*program, false));
+        let (rule, metas) = wallet.rule_check(DEFAULT_RULE_ID, remaining.len() as u8);
+        remaining.extend(metas);
+        args.batch.push(BatchCpiData {
+            program_index,
+            rules: vec![rule],
+            cpi_data: cpi_data(data.clone(), remaining.len() as u8, 0),
+        });
+    }
+    (args, remaining)
+}
+
+const BLOCKED_PROGRAM: Pubkey = pubkey!("B1ocked111111111111111111111111111111111111");
+
+/// Rule rejecting any CPI to `BLOCKED_PROGRAM`
+fn blocking_rule(instruction: &Instruction) -> ProgramResult {
+    match CheckRuleArgs::try_from_instruction_data(&instruction.data) {
+        Some(check) if check.program_id == BLOCKED_PROGRAM => Err(ProgramError::Custom(3)),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn every_batch_cpi_passes_the_rules_before_any_runs() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    wallet.ledger.set_program(first);
+    wallet.ledger.set_program(second);
+    let (args, remaining) = batch(&wallet, &[(first, vec![1]), (second, vec![2])]);
+
+    wallet
+        .execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+        .unwrap();
+
+    let cpis = take_cpis();
+    let programs: Vec<_> = cpis.iter().map(|cpi| cpi.program_id).collect();
+    assert_eq!(
+        programs,
+        vec![DEFAULT_RULE_ID, DEFAULT_RULE_ID, first, second]
+    );
+    for (cpi, program) in cpis.iter().zip([first, second]) {
+        let check = CheckRuleArgs::try_from_instruction_data(&cpi.data).unwrap();
+        assert_eq!(check.program_id, program);
+    }
+    assert_eq!(cpis[2].data, vec![1]);
+    assert_eq!(cpis[3].data, vec![2]);
+}
+
+#[test]
+fn a_batch_cpi_rejected_by_a_rule_keeps_the_others_from_running() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let first = Pubkey::new_unique();
+    wallet.ledger.set_program(first);
+    wallet.ledger.set_program(BLOCKED_PROGRAM);
+    stub_program(DEFAULT_RULE_ID, blocking_rule);
+    let (args, remaining) = batch(&wallet, &[(first, vec![1]), (BLOCKED_PROGRAM, vec![2])]);
+
+    let result = wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &remaining);
+
+    assert_eq!(result, Err(ProgramError::Custom(3)));
+    let programs: Vec<_> = take_cpis().iter().map(|cpi| cpi.program_id).collect();
+    assert_eq!(programs, vec![DEFAULT_RULE_ID, DEFAULT_RULE_ID]);
+    assert_eq!(wallet.config().nonce, 0);
+}
+
+#[test]
+fn changing_one_batch_cpi_invalidates_the_signature() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    wallet.ledger.set_program(first);
+    wallet.ledger.set_program(second);
+
+    let alterations: [fn(&mut ExecuteInstructionArgs); 3] = [
+        |args| args.batch[1].cpi_data.data = vec![3],
+        |args| args.batch[1].program_index = args.batch[0].program_index,
+        |args| {
+            args.batch.pop();
+        },
+    ];
+    for alter in alterations {
+        let (args, remaining) = batch(&wallet, &[(first, vec![1]), (second, vec![2])]);
+
+        let result = wallet.execute_altered(args, DEFAULT_RULE_ACCOUNTS, &remaining, alter);
+
+        assert_eq!(result, Err(program_error(LazorKitError::ChallengeMismatch)));
+        assert!(take_cpis().is_empty());
+    }
+}
+
+#[test]
+fn other_cpis_need_user_verification_whatever_the_threshold() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    wallet.set_config(|config| config.verification_policy = policy(0, u64::MAX));
+    let token_program = Pubkey::new_unique();
+    wallet.ledger.set_program(token_program);
+    wallet.user_verified = false;
+
+    assert_eq!(
+        execute_cpi(
+            &mut wallet,
+            token_program,
+            vec![3, 1, 0, 0, 0, 0, 0, 0, 0],
+            vec![]
+        ),
+        Err(program_error(LazorKitError::UserNotVerified))
+    );
+
+    wallet.user_verified = true;
+    execute_cpi(
+        &mut wallet,
+        token_program,
+        vec![3, 1, 0, 0, 0, 0, 0, 0, 0],
+        vec![],
+    )
+    .unwrap();
+    assert_eq!(take_cpis().last().unwrap().program_id, token_program);
+}
+
+#[test]
+fn stale_or_replayed_nonces_are_rejected() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let executed = wallet.args(Action::CheckAuthenticator);
+    wallet
+        .execute(executed.clone(), DEFAULT_RULE_ACCOUNTS, &[])
+        .unwrap();
+    assert_eq!(wallet.config().nonce, 1);
+
+    // The executed message replayed, and one signed ahead of the wallet's nonce
+    for nonce in [0, 2] {
+        let mut args = executed.clone();
+        args.nonce = nonce;
+
+        let result = wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &[]);
+
+        assert_eq!(result, Err(program_error(LazorKitError::InvalidNonce)));
+    }
+    assert_eq!(wallet.config().nonce, 1);
+}
+
+#[test]
+fn expired_messages_are_rejected() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    set_unix_timestamp(1_700_000_000);
+    let mut args = wallet.args(Action::CheckAuthenticator);
+    args.expiry = Some(Expiry::UnixTimestamp(1_699_999_999));
+
+    let result = wallet.execute(args.clone(), DEFAULT_RULE_ACCOUNTS, &[]);
+
+    assert_eq!(result, Err(program_error(LazorKitError::MessageExpired)));
+    assert_eq!(wallet.config().nonce, 0);
+
+    // Still valid up to the expiry itself
+    args.expiry = Some(Expiry::UnixTimestamp(1_700_000_000));
+    wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &[]).unwrap();
+    assert_eq!(wallet.config().nonce, 1);
+}
+
+#[test]
+fn sign_count_regression_flags_the_authenticator_instead_of_executing() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let authenticator = wallet.authenticator_key(&wallet.passkey).0;
+    let (args, remaining) = call_rule_program(&wallet, vec![9, 9]);
+    wallet
+        .execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+        .unwrap();
+    take_cpis();
+    take_events();
+
+    wallet.sign_count = 1;
+    let (args, remaining) = call_rule_program(&wallet, vec![9, 9]);
+    wallet
+        .execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+        .unwrap();
+
+    // The transaction succeeds so that the flag and the event land, but does nothing else
+    assert!(take_cpis().is_empty());
+    let events = take_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(&events[0][..8], SignCountRegression::DISCRIMINATOR);
+    let event = SignCountRegression::try_from_slice(&events[0][8..]).unwrap();
+    assert_eq!(event.smart_wallet, wallet.smart_wallet);
+    assert_eq!(event.smart_wallet_authenticator, authenticator);
+    assert_eq!(event.stored_sign_count, 1);
+    assert_eq!(event.received_sign_count, 1);
+    let stored = wallet.authenticator(&wallet.passkey).unwrap();
+    assert!(stored.clone_suspected);
+    assert_eq!(stored.sign_count, 1);
+    assert_eq!(wallet.config().nonce, 2);
+
+    // The flagged authenticator can no longer be used, whatever counter it reports
+    wallet.sign_count = 10;
+    let (args, remaining) = call_rule_program(&wallet, vec![9, 9]);
+    assert_eq!(
+        wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &remaining),
+        Err(program_error(LazorKitError::SignCountRegression))
+    );
+    assert!(take_cpis().is_empty());
+}
+
+#[test]
+fn every_remaining_account_is_bound_to_the_signature() {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let (args, mut signed) = call_rule_program(&wallet, vec![9, 9]);
+    signed.push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+    let mut sent = signed.clone();
+    sent[2].pubkey = Pubkey::new_unique();
+
+    let result = wallet.execute_with_remaining(args, DEFAULT_RULE_ACCOUNTS, &signed, &sent);
+
+    assert_eq!(result, Err(program_error(LazorKitError::ChallengeMismatch)));
+    assert!(take_cpis().is_empty());
+}
+
+#[test]
+fn authenticator_program_must_be_one_of_the_wallet_rules() {
+    for authenticator_program in [TRANSFER_LIMIT_ID, Pubkey::new_unique()] {
+        let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+        wallet.ledger.set_program(authenticator_program);
+        let (args, remaining) = call_rule_program(&wallet, vec![9, 9]);
+
+        let result = wallet.execute(
+            args,
+            ExecuteAccounts {
+                authenticator_program,
+                cpi_program: system_program::ID,
+            },
+            &remaining,
+        );
+
+        assert_eq!(
+            result,
+            Err(program_error(LazorKitError::InvalidRuleProgram))
+        );
+        assert!(take_cpis().is_empty());
+        assert_eq!(wallet.config().nonce, 0);
+    }
+}
+
+/// `RevokeAuthenticator` of `passkey`, giving every wallet rule its
+/// [SmartWalletAuthenticator, RevokedAuthenticator] accounts
+fn revoke_authenticator(wallet: &mut Wallet, passkey: [u8; 33]) -> ProgramResult {
+    let signer = wallet.authenticator_key(&wallet.passkey).0;
+    let revoked = wallet.authenticator_key(&passkey).0;
+    let mut remaining = vec![];
+    let mut rules = vec![];
+    for program in wallet.config().rule_programs {
+        let start = remaining.len() as u8;
+        remaining.push(AccountMeta::new_readonly(program, false));
+        remaining.push(AccountMeta::new_readonly(signer, false));
+        remaining.push(AccountMeta::new(revoked, false));
+        rules.push(rule_accounts(start, start + 1, 2));
+    }
+    let mut args = wallet.args(Action::RevokeAuthenticator);
+    args.rules = rules;
+    args.revoke_authenticator = Some(passkey);
+    wallet.execute(args, DEFAULT_RULE_ACCOUNTS, &remaining)
+}
+
+/// Wallet with a second authenticator for `SECOND_PASSKEY`, governed by `rule_programs`
+fn wallet_with_two_authenticators(rule_programs: &[Pubkey]) -> Wallet {
+    let mut wallet = Wallet::new(rule_programs);
+    wallet.add_authenticator(SECOND_PASSKEY);
+    wallet.set_config(|config| config.authenticator_count = 2);
+    wallet
+}
+
+#[test]
+fn revoking_an_authenticator_reaches_a_delisted_rule() {
+    let mut wallet = wallet_with_two_authenticators(&[DEFAULT_RULE_ID, THIRD_RULE_ID]);
+    wallet.delist(THIRD_RULE_ID);
+
+    revoke_authenticator(&mut wallet, SECOND_PASSKEY).unwrap();
+
+    let programs: Vec<_> = take_cpis().iter().map(|cpi| cpi.program_id).collect();
+    assert_eq!(programs, vec![DEFAULT_RULE_ID, THIRD_RULE_ID]);
+    assert!(wallet.authenticator(&SECOND_PASSKEY).is_none());
+}
+
+#[test]
+fn revoking_calls_every_rule_then_refunds_the_wallet() {
+    let mut wallet = wallet_with_two_authenticators(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+    let signer = wallet.authenticator_key(&wallet.passkey).0;
+    let revoked = wallet.authenticator_key(&SECOND_PASSKEY).0;
+    let wallet_lamports = wallet.ledger.lamports(&wallet.smart_wallet);
+    let payer_lamports = wallet.ledger.lamports(&wallet.payer);
+    let rent = wallet.ledger.lamports(&revoked);
+
+    revoke_authenticator(&mut wallet, SECOND_PASSKEY).unwrap();
+
+    let cpis = take_cpis();
+    let calls: Vec<_> = cpis
+        .iter()
+        .map(|cpi| (cpi.program_id, RuleInstruction::parse(&cpi.data)))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            (DEFAULT_RULE_ID, Some(RuleInstruction::RevokeAuthenticator)),
+            (
+                TRANSFER_LIMIT_ID,
+                Some(RuleInstruction::RevokeAuthenticator)
+            ),
+        ]
+    );
+    for cpi in &cpis {
+        assert_eq!(cpi.accounts[0].pubkey, signer);
+        assert!(cpi.accounts[0].is_signer);
+        assert_eq!(cpi.accounts[1].pubkey, revoked);
+    }
+    // The wallet receives the rent, less what it reimburses the payer
+    assert!(wallet.authenticator(&SECOND_PASSKEY).is_none());
+    let reimbursed = wallet.ledger.lamports(&wallet.payer) - payer_lamports;
+    assert_eq!(
+        wallet.ledger.lamports(&wallet.smart_wallet),
+        wallet_lamports + rent - reimbursed
+    );
+    assert_eq!(wallet.config().authenticator_count, 1);
+}
+
+#[test]
+fn the_last_authenticator_cannot_be_revoked() {
+    // A second authenticator the config does not count
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    wallet.add_authenticator(SECOND_PASSKEY);
+
+    let result = revoke_authenticator(&mut wallet, SECOND_PASSKEY);
+
+    assert_eq!(result, Err(program_error(LazorKitError::LastAuthenticator)));
+    assert!(take_cpis().is_empty());
+    assert!(wallet.authenticator(&SECOND_PASSKEY).is_some());
+}
+
+#[test]
+fn an_authenticator_cannot_revoke_itself() {
+    let mut wallet = wallet_with_two_authenticators(&[DEFAULT_RULE_ID]);
+
+    let result = revoke_authenticator(&mut wallet, PASSKEY);
+
+    assert_eq!(
+        result,
+        Err(program_error(LazorKitError::InvalidAuthenticator))
+    );
+    assert!(wallet.authenticator(&PASSKEY).is_some());
+    assert_eq!(wallet.config().authenticator_count, 2);
+}
+
+#[test]
+fn a_rule_refusing_the_revocation_keeps_the_authenticator() {
+    let mut wallet = wallet_with_two_authenticators(&[DEFAULT_RULE_ID, TRANSFER_LIMIT_ID]);
+    fail_cpis_to(TRANSFER_LIMIT_ID);
+
+    let result = revoke_authenticator(&mut wallet, SECOND_PASSKEY);
+
+    assert_eq!(result, Err(ProgramError::Custom(1)));
+    assert!(wallet.authenticator(&SECOND_PASSKEY).is_some());
+    assert_eq!(wallet.config().authenticator_count, 2);
+}
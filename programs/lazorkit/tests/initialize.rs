@@ -0,0 +1,165 @@
+#![allow(deprecated)]
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, entrypoint::ProgramResult};
+use anchor_lang::{system_program, InstructionData, Space};
+use common::*;
+use lazorkit::{
+    constants::AUTHORITY_SEED,
+    error::LazorKitError,
+    state::{Config, RelyingParty, SmartWalletSeq, WhitelistRulePrograms},
+    ID,
+};
+
+/// A freshly deployed lazorkit whose upgrade authority is `upgrade_authority`
+struct Deployment {
+    ledger: Ledger,
+    program_data: Pubkey,
+}
+
+impl Deployment {
+    fn new(upgrade_authority: Pubkey) -> Self {
+        let mut ledger = Ledger::default();
+        let program_data =
+            Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID).0;
+
+        // UpgradeableLoaderState::Program { programdata_address }
+        ledger.set(
+            ID,
+            AccountState {
+                lamports: 1,
+                data: [&2u32.to_le_bytes()[..], program_data.as_ref()].concat(),
+                owner: bpf_loader_upgradeable::ID,
+                executable: true,
+            },
+        );
+        // UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }
+        ledger.set(
+            program_data,
+            AccountState {
+                lamports: 1,
+                data: [
+                    &3u32.to_le_bytes()[..],
+                    &0u64.to_le_bytes(),
+                    &[1],
+                    upgrade_authority.as_ref(),
+                ]
+                .concat(),
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+        ledger.set_program(system_program::ID);
+        ledger.set_program(DEFAULT_RULE_ID);
+        Self {
+            ledger,
+            program_data,
+        }
+    }
+
+    fn initialize(&mut self, signer: Pubkey) -> ProgramResult {
+        self.ledger.set(
+            signer,
+            AccountState {
+                lamports: 1_000_000_000,
+                ..Default::default()
+            },
+        );
+        let pda = |seed: &[u8]| Pubkey::find_program_address(&[seed], &ID).0;
+        let metas = [
+            AccountMeta::new(signer, true),
+            AccountMeta::new(pda(Config::PREFIX_SEED), false),
+            AccountMeta::new(pda(WhitelistRulePrograms::PREFIX_SEED), false),
+            AccountMeta::new(pda(SmartWalletSeq::PREFIX_SEED), false),
+            AccountMeta::new(pda(AUTHORITY_SEED), false),
+            AccountMeta::new_readonly(DEFAULT_RULE_ID, false),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(self.program_data, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ];
+        let data = lazorkit::instruction::Initialize {
+            relying_parties: vec![RelyingParty {
+                rp_id_hash: [1; 32],
+                origin: ORIGIN.to_string(),
+            }],
+        }
+        .data();
+        self.ledger.process(&metas, &data)
+    }
+}
+
+#[test]
+fn initialize_creates_the_program_accounts() {
+    let authority = Pubkey::new_unique();
+    let mut deployment = Deployment::new(authority);
+
+    deployment.initialize(authority).unwrap();
+
+    let ledger = &deployment.ledger;
+    let config: Config = ledger
+        .anchor_account(&Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID).0)
+        .unwrap();
+    assert_eq!(config.admin, authority);
+    assert_eq!(config.treasury, authority);
+    assert_eq!(config.default_rule_program, DEFAULT_RULE_ID);
+    assert_eq!(config.relying_parties.len(), 1);
+    let (whitelist_key, whitelist_bump) =
+        Pubkey::find_program_address(&[WhitelistRulePrograms::PREFIX_SEED], &ID);
+    let whitelist: WhitelistRulePrograms = ledger.anchor_account(&whitelist_key).unwrap();
+    assert_eq!(whitelist.list, vec![DEFAULT_RULE_ID]);
+    assert_eq!(whitelist.bump, whitelist_bump);
+    let seq: SmartWalletSeq = ledger
+        .anchor_account(&Pubkey::find_program_address(&[SmartWalletSeq::PREFIX_SEED], &ID).0)
+        .unwrap();
+    assert_eq!(seq.seq, 0);
+    let authority_account =
+        &ledger.accounts[&Pubkey::find_program_address(&[AUTHORITY_SEED], &ID).0];
+    assert_eq!(authority_account.owner, ID);
+}
+
+#[test]
+fn initialize_twice_fails_with_already_initialized() {
+    let authority = Pubkey::new_unique();
+    let mut deployment = Deployment::new(authority);
+    deployment.initialize(authority).unwrap();
+
+    assert_eq!(
+        deployment.initialize(authority),
+        Err(program_error(LazorKitError::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn initialize_rejects_a_signer_other_than_the_upgrade_authority() {
+    let mut deployment = Deployment::new(Pubkey::new_unique());
+
+    assert_eq!(
+        deployment.initialize(Pubkey::new_unique()),
+        Err(program_error(LazorKitError::InvalidUpgradeAuthority))
+    );
+}
+
+#[test]
+fn initialize_tops_up_a_prefunded_config() {
+    let authority = Pubkey::new_unique();
+    let mut deployment = Deployment::new(authority);
+    let config_key = Pubkey::find_program_address(&[Config::PREFIX_SEED], &ID).0;
+    deployment.ledger.set(
+        config_key,
+        AccountState {
+            lamports: 1_000,
+            ..Default::default()
+        },
+    );
+
+    deployment.initialize(authority).unwrap();
+
+    let config: Config = deployment.ledger.anchor_account(&config_key).unwrap();
+    assert_eq!(config.admin, authority);
+    assert_eq!(
+        deployment.ledger.lamports(&config_key),
+        Rent::default().minimum_balance(8 + Config::INIT_SPACE)
+    );
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{system_program, Discriminator, InstructionData};
+use common::*;
+use lazorkit::{
+    error::LazorKitError,
+    state::{SmartWalletAuthenticator, SmartWalletConfig},
+};
+
+/// Wallet whose config and authenticators for `passkeys` are in the legacy layouts
+fn legacy_wallet(passkeys: &[[u8; 33]]) -> Wallet {
+    let mut wallet = Wallet::new(&[DEFAULT_RULE_ID]);
+    let config = wallet.config();
+    wallet.ledger.set(
+        wallet.smart_wallet_config,
+        legacy_account(
+            [
+                SmartWalletConfig::DISCRIMINATOR,
+                &config.id.to_le_bytes(),
+                DEFAULT_RULE_ID.as_ref(),
+                &[config.bump],
+            ]
+            .concat(),
+        ),
+    );
+    for passkey in passkeys {
+        let (key, bump) = wallet.authenticator_key(passkey);
+        let authenticator = legacy_authenticator(passkey, wallet.smart_wallet, bump);
+        wallet.ledger.set(key, authenticator);
+    }
+    wallet
+}
+
+fn legacy_authenticator(passkey: &[u8; 33], smart_wallet: Pubkey, bump: u8) -> AccountState {
+    legacy_account(
+        [
+            SmartWalletAuthenticator::DISCRIMINATOR,
+            passkey,
+            smart_wallet.as_ref(),
+            &[bump],
+        ]
+        .concat(),
+    )
+}
+
+fn legacy_account(data: Vec<u8>) -> AccountState {
+    AccountState {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: lazorkit::ID,
+        executable: false,
+    }
+}
+
+fn migrate_smart_wallet(wallet: &mut Wallet, passkeys: &[[u8; 33]]) -> ProgramResult {
+    let mut metas = vec![
+        AccountMeta::new(wallet.payer, true),
+        AccountMeta::new_readonly(wallet.smart_wallet, false),
+        AccountMeta::new(wallet.smart_wallet_config, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    metas.extend(
+        passkeys
+            .iter()
+            .map(|passkey| AccountMeta::new(wallet.authenticator_key(passkey).0, false)),
+    );
+    let data = lazorkit::instruction::MigrateSmartWallet {}.data();
+    wallet.ledger.process(&metas, &data)
+}
+
+fn migrate_authenticator(wallet: &mut Wallet, passkey: &[u8; 33]) -> ProgramResult {
+    let metas = [
+        AccountMeta::new(wallet.payer, true),
+        AccountMeta::new_readonly(wallet.smart_wallet, false),
+        AccountMeta::new(wallet.smart_wallet_config, false),
+        AccountMeta::new(wallet.authenticator_key(passkey).0, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    let data = lazorkit::instruction::MigrateAuthenticator {}.data();
+    wallet.ledger.process(&metas, &data)
+}
+
+#[test]
+fn authenticators_left_out_of_the_wallet_migration_are_migrated_later() {
+    let mut wallet = legacy_wallet(&[PASSKEY, SECOND_PASSKEY]);
+
+    migrate_smart_wallet(&mut wallet, &[PASSKEY]).unwrap();
+
+    let config = wallet.config();
+    assert_eq!(config.version, SmartWalletConfig::VERSION);
+    assert_eq!(config.rule_programs, vec![DEFAULT_RULE_ID]);
+    assert_eq!(config.authenticator_count, 1);
+    assert_eq!(wallet.authenticator(&PASSKEY).unwrap().sign_count, 0);
+    assert!(wallet.authenticator(&SECOND_PASSKEY).is_none());
+
+    migrate_authenticator(&mut wallet, &SECOND_PASSKEY).unwrap();
+
+    let authenticator = wallet.authenticator(&SECOND_PASSKEY).unwrap();
+    assert_eq!(authenticator.passkey_pubkey, SECOND_PASSKEY);
+    assert_eq!(authenticator.smart_wallet, wallet.smart_wallet);
+    assert_eq!(wallet.config().authenticator_count, 2);
+}
+
+#[test]
+fn an_authenticator_is_migrated_only_once() {
+    let mut wallet = legacy_wallet(&[PASSKEY, SECOND_PASSKEY]);
+    migrate_smart_wallet(&mut wallet, &[PASSKEY, SECOND_PASSKEY]).unwrap();
+
+    for passkey in [PASSKEY, SECOND_PASSKEY] {
+        assert_eq!(
+            migrate_authenticator(&mut wallet, &passkey),
+            Err(program_error(LazorKitError::AccountAlreadyMigrated))
+        );
+    }
+    assert_eq!(wallet.config().authenticator_count, 2);
+}
+
+#[test]
+fn authenticators_are_migrated_after_their_wallet() {
+    let mut wallet = legacy_wallet(&[PASSKEY]);
+
+    assert!(migrate_authenticator(&mut wallet, &PASSKEY).is_err());
+    assert!(wallet.authenticator(&PASSKEY).is_none());
+}
+
+#[test]
+fn only_the_wallet_s_own_authenticators_are_migrated() {
+    let mut wallet = legacy_wallet(&[PASSKEY]);
+    migrate_smart_wallet(&mut wallet, &[PASSKEY]).unwrap();
+    let (key, bump) = wallet.authenticator_key(&SECOND_PASSKEY);
+    let foreign = legacy_authenticator(&SECOND_PASSKEY, Pubkey::new_unique(), bump);
+    wallet.ledger.set(key, foreign);
+
+    assert_eq!(
+        migrate_authenticator(&mut wallet, &SECOND_PASSKEY),
+        Err(program_error(LazorKitError::InvalidAccountInput))
+    );
+    assert_eq!(wallet.config().authenticator_count, 1);
+}
@@ -25,4 +25,28 @@ pub enum TransferLimitError {
     MemberNotInitialized,
 
     TransferAmountExceedLimit,
+
+    InvalidLimitPeriod,
+
+    TooManyMembers,
+
+    MemberNotFound,
+
+    LastAdmin,
+
+    LabelTooLong,
+
+    RecipientNotPermitted,
+
+    InvalidMint,
+
+    TooManyTokenLimits,
+
+    RuleAccountsLeftOpen,
+
+    InvalidAuthority,
+
+    AlreadyInitialized,
+
+    InvalidUpgradeAuthority,
 }
@@ -3,15 +3,10 @@ use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator, utils::Passke
 
 use crate::{
     errors::TransferLimitError,
-    state::{Member, MemberType},
+    state::{Member, MemberList, MemberType, RecipientMode},
     ID,
 };
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct AddMemberArgs {
-    pub member: Pubkey,
-}
-
 pub fn add_member(ctx: Context<AddMember>, new_passkey_pubkey: [u8; 33], bump: u8) -> Result<()> {
     let member = &mut ctx.accounts.member;
     let new_smart_wallet_authenticator = &mut ctx.accounts.new_smart_wallet_authenticator;
@@ -35,7 +30,11 @@ pub fn add_member(ctx: Context<AddMember>, new_passkey_pubkey: [u8; 33], bump: u
         smart_wallet: smart_wallet_authenticator.smart_wallet,
         bump: expected_bump,
         is_initialized: true,
+        recipient_mode: RecipientMode::Unrestricted,
     });
+    ctx.accounts
+        .member_list
+        .add(new_smart_wallet_authenticator.key(), MemberType::Member)?;
 
     Ok(())
 }
@@ -75,6 +74,14 @@ pub struct AddMember<'info> {
     )]
     pub member: Account<'info, Member>,
 
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
     pub lazorkit: Program<'info, Lazorkit>,
 
     pub system_program: Program<'info, System>,
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    state::{Member, MemberList, MemberType, Recipient, RecipientKind},
+    ID,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AddRecipientArgs {
+    pub address: Pubkey,
+    pub label: String,
+    pub kind: RecipientKind,
+}
+
+pub fn add_recipient(ctx: Context<AddRecipient>, args: AddRecipientArgs) -> Result<()> {
+    require!(
+        args.label.len() <= Recipient::MAX_LABEL_LEN,
+        TransferLimitError::LabelTooLong
+    );
+
+    ctx.accounts.recipient.set_inner(Recipient {
+        smart_wallet: ctx.accounts.smart_wallet_authenticator.smart_wallet,
+        address: args.address,
+        label: args.label,
+        kind: args.kind,
+        bump: ctx.bumps.recipient,
+    });
+
+    let member_list = &mut ctx.accounts.member_list;
+    member_list.recipient_count = member_list
+        .recipient_count
+        .checked_add(1)
+        .ok_or(TransferLimitError::InvalidAccountInput)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(args: AddRecipientArgs)]
+pub struct AddRecipient<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Recipient::INIT_SPACE,
+        seeds = [Recipient::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), args.address.as_ref()],
+        bump,
+    )]
+    pub recipient: Box<Account<'info, Recipient>>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    spend::token_decimals,
+    state::{Member, MemberList, MemberType, RuleData},
+    ID,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AddTokenLimitArgs {
+    pub token: Option<Pubkey>,
+    pub limit_amount: u64,
+    pub limit_period: u64,
+}
+
+pub fn add_token_limit(ctx: Context<AddTokenLimit>, args: AddTokenLimitArgs) -> Result<()> {
+    let rule_data = &mut ctx.accounts.rule_data;
+    rule_data.set_inner(RuleData {
+        token: args.token,
+        decimals: token_decimals(args.token, ctx.accounts.mint.as_deref())?,
+        bump: ctx.bumps.rule_data,
+        is_initialized: true,
+        ..Default::default()
+    });
+    rule_data.set_limit(args.limit_amount, args.limit_period)?;
+    ctx.accounts.member_list.add_token(args.token)
+}
+
+#[derive(Accounts)]
+#[instruction(args: AddTokenLimitArgs)]
+pub struct AddTokenLimit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RuleData::INIT_SPACE,
+        seeds = [RuleData::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), args.token.unwrap_or_default().as_ref()],
+        bump,
+    )]
+    pub rule_data: Box<Account<'info, RuleData>>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Mint of `args.token`, read for its decimals. Omitted for SOL
+    pub mint: Option<UncheckedAccount<'info>>,
+}
@@ -1,24 +1,27 @@
 use anchor_lang::prelude::*;
-use anchor_lang::system_program::ID as SYSTEM_ID;
-use anchor_spl::token::ID as SPL_TOKEN;
-use lazorkit::{
-    constants::SOL_TRANSFER_DISCRIMINATOR, program::Lazorkit, state::SmartWalletAuthenticator,
-};
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+use rule_interface::CheckRuleArgs;
 
 use crate::{
     errors::TransferLimitError,
-    state::{Member, MemberType, RuleData},
+    spend::Spend,
+    state::{Member, MemberType, Recipient, RecipientKind, RecipientMode, Record, RuleData},
     ID,
 };
 
-pub fn check_rule(
-    ctx: Context<CheckRule>,
-    _token: Option<Pubkey>,
-    cpi_data: Vec<u8>,
-    program_id: Pubkey,
-) -> Result<()> {
+pub fn check_rule(ctx: Context<CheckRule>, args: CheckRuleArgs) -> Result<()> {
     let member = &ctx.accounts.member;
     let rule_data = &ctx.accounts.rule_data;
+    let record = &mut ctx.accounts.record;
+    if record.smart_wallet == Pubkey::default() {
+        record.set_inner(Record {
+            smart_wallet: ctx.accounts.smart_wallet_authenticator.smart_wallet,
+            token: rule_data.token,
+            member: ctx.accounts.smart_wallet_authenticator.key(),
+            bump: ctx.bumps.record,
+            ..Default::default()
+        });
+    }
 
     // check if admin or not initialized
     require!(
@@ -27,32 +30,62 @@ pub fn check_rule(
     );
 
     if member.member_type != MemberType::Admin && rule_data.is_initialized {
-        // check program_id must equal system program, token program or token 2022 program
-        if program_id != SYSTEM_ID && program_id != SPL_TOKEN {
-            return Err(TransferLimitError::UnAuthorize.into());
-        } else {
-            if cpi_data.get(0..4) == Some(&SOL_TRANSFER_DISCRIMINATOR) && program_id == SYSTEM_ID {
-                let amount = u64::from_le_bytes(cpi_data[4..12].try_into().unwrap());
-                if amount > rule_data.limit_amount {
-                    return Err(TransferLimitError::TransferAmountExceedLimit.into());
-                }
-            } else if cpi_data.get(0..4) == Some(&SOL_TRANSFER_DISCRIMINATOR)
-                && program_id == SPL_TOKEN
+        // members may only move value the rule limits
+        let spend = Spend::decode(&args).ok_or(TransferLimitError::UnAuthorize)?;
+        require!(
+            spend.resolve_mint(ctx.remaining_accounts, &args.program_id)? == rule_data.token,
+            TransferLimitError::InvalidToken
+        );
+        if member.recipient_mode != RecipientMode::Unrestricted {
+            if let Some(recipient) =
+                spend.resolve_recipient(ctx.remaining_accounts, &args.program_id)?
             {
-                let amount = u64::from_le_bytes(cpi_data[4..12].try_into().unwrap());
-                if amount > rule_data.limit_amount {
-                    return Err(TransferLimitError::TransferAmountExceedLimit.into());
-                }
-            } else {
-                return Err(TransferLimitError::UnAuthorize.into());
+                let kind = address_book_entry(
+                    ctx.remaining_accounts,
+                    &ctx.accounts.smart_wallet_authenticator.smart_wallet,
+                    &recipient,
+                )?;
+                require!(
+                    member.recipient_mode.permits(kind),
+                    TransferLimitError::RecipientNotPermitted
+                );
             }
         }
+        record.spend(
+            spend.normalized_amount(rule_data.decimals)?,
+            rule_data.limit_amount,
+            rule_data.limit_period,
+            Clock::get()?.unix_timestamp,
+        )?;
     }
     Ok(())
 }
 
+/// How `recipient` is listed in the wallet's address book. Its recipient account must be
+/// passed in `accounts` even when it does not exist, proving the recipient is not listed
+fn address_book_entry(
+    accounts: &[AccountInfo],
+    smart_wallet: &Pubkey,
+    recipient: &Pubkey,
+) -> Result<Option<RecipientKind>> {
+    let key = Recipient::address(smart_wallet, recipient);
+    let account = accounts
+        .iter()
+        .find(|account| *account.key == key)
+        .ok_or(TransferLimitError::InvalidAccountInput)?;
+    if account.owner == &ID {
+        let entry = Recipient::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        Ok(Some(entry.kind))
+    } else {
+        require!(
+            account.data_is_empty(),
+            TransferLimitError::InvalidAccountInput
+        );
+        Ok(None)
+    }
+}
+
 #[derive(Accounts)]
-#[instruction(token: Option<Pubkey>)]
 pub struct CheckRule<'info> {
     #[account(
         owner = lazorkit.key(),
@@ -60,6 +93,9 @@ pub struct CheckRule<'info> {
     )]
     pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
 
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     #[account(
         seeds = [Member::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
         bump,
@@ -68,11 +104,22 @@ pub struct CheckRule<'info> {
     pub member: Account<'info, Member>,
 
     #[account(
-        seeds = [RuleData::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), token.as_ref().unwrap_or(&Pubkey::default()).as_ref()],
+        seeds = [RuleData::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), rule_data.token.unwrap_or_default().as_ref()],
         bump,
         owner = ID,
     )]
     pub rule_data: Box<Account<'info, RuleData>>,
 
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Record::INIT_SPACE,
+        seeds = [Record::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), rule_data.token.unwrap_or_default().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+    )]
+    pub record: Box<Account<'info, Record>>,
+
     pub lazorkit: Program<'info, Lazorkit>,
+
+    pub system_program: Program<'info, System>,
 }
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    state::{Member, MemberList, MemberType, Recipient, Record, RuleData},
+    ID,
+};
+
+/// Leave the rule, closing the wallet's member list and the signing admin's member account
+/// together with every other member, rule data, record and recipient account of the wallet
+/// passed as remaining accounts.
+///
+/// Every member, rule data and recipient account the member list accounts for must be
+/// passed, so that none is left behind for a later rule of the wallet to pick up. Records
+/// are closed when passed; one left behind only carries its spend over to a later rule.
+pub fn destroy<'c: 'info, 'info>(ctx: Context<'_, '_, 'c, 'info, Destroy<'info>>) -> Result<()> {
+    let smart_wallet = ctx.accounts.smart_wallet.to_account_info();
+    let member_list = &ctx.accounts.member_list;
+    let admin = ctx.accounts.smart_wallet_authenticator.key();
+    let mut members_left = member_list
+        .members
+        .iter()
+        .map(|entry| entry.authenticator)
+        .filter(|authenticator| *authenticator != admin)
+        .collect::<Vec<_>>();
+    let mut tokens_left = member_list.tokens.clone();
+    let mut recipients_left = member_list.recipient_count;
+
+    for account in ctx.remaining_accounts {
+        require!(
+            account.owner == &ID && account.is_writable,
+            TransferLimitError::InvalidAccountInput
+        );
+        if let Ok(member) = Account::<Member>::try_from(account) {
+            require!(
+                member.smart_wallet == smart_wallet.key(),
+                TransferLimitError::InvalidAccountInput
+            );
+            members_left.retain(|authenticator| *authenticator != member.owner);
+            member.close(smart_wallet.clone())?;
+        } else if let Ok(record) = Account::<Record>::try_from(account) {
+            require!(
+                record.smart_wallet == smart_wallet.key(),
+                TransferLimitError::InvalidAccountInput
+            );
+            record.close(smart_wallet.clone())?;
+        } else if let Ok(recipient) = Account::<Recipient>::try_from(account) {
+            require!(
+                recipient.smart_wallet == smart_wallet.key(),
+                TransferLimitError::InvalidAccountInput
+            );
+            recipients_left = recipients_left.saturating_sub(1);
+            recipient.close(smart_wallet.clone())?;
+        } else {
+            let rule_data = Account::<RuleData>::try_from(account)?;
+            let (expected, _) = Pubkey::find_program_address(
+                &[
+                    RuleData::PREFIX_SEED,
+                    smart_wallet.key().as_ref(),
+                    rule_data.token.unwrap_or_default().as_ref(),
+                ],
+                &ID,
+            );
+            require!(
+                expected == rule_data.key(),
+                TransferLimitError::InvalidRuleAccount
+            );
+            tokens_left.retain(|token| *token != rule_data.token);
+            rule_data.close(smart_wallet.clone())?;
+        }
+    }
+
+    require!(
+        members_left.is_empty() && tokens_left.is_empty() && recipients_left == 0,
+        TransferLimitError::RuleAccountsLeftOpen
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Destroy<'info> {
+    #[account(
+        mut,
+        address = smart_wallet_authenticator.smart_wallet,
+    )]
+    /// CHECK: Receives the rent of the closed accounts
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        mut,
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+        close = smart_wallet,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+        close = smart_wallet,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -6,7 +6,7 @@ use lazorkit::{
     utils::PasskeyExt,
 };
 
-use crate::state::*;
+use crate::{spend::token_decimals, state::*};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct InitRuleArgs {
@@ -16,25 +16,37 @@ pub struct InitRuleArgs {
     pub limit_period: u64,
 }
 
+/// Attach the rule to a wallet, making the signing authenticator its admin. Fails once the
+/// rule is attached: further token limits go through `add_token_limit`.
 pub fn init_rule(ctx: Context<InitRule>, args: InitRuleArgs) -> Result<()> {
     let rule_data = &mut ctx.accounts.rule_data;
     rule_data.set_inner(RuleData {
         token: args.token,
-        limit_amount: args.limit_amount,
+        decimals: token_decimals(args.token, ctx.accounts.mint.as_deref())?,
         bump: ctx.bumps.smart_wallet_authenticator,
         is_initialized: true,
+        ..Default::default()
     });
+    rule_data.set_limit(args.limit_amount, args.limit_period)?;
 
     let member = &mut ctx.accounts.member;
-    if !member.is_initialized {
-        member.set_inner(Member {
-            smart_wallet: ctx.accounts.smart_wallet.key(),
-            owner: ctx.accounts.smart_wallet_authenticator.key(),
-            bump: ctx.bumps.smart_wallet_authenticator,
-            is_initialized: true,
-            member_type: MemberType::Admin,
-        });
-    }
+    member.set_inner(Member {
+        smart_wallet: ctx.accounts.smart_wallet.key(),
+        owner: ctx.accounts.smart_wallet_authenticator.key(),
+        bump: ctx.bumps.smart_wallet_authenticator,
+        is_initialized: true,
+        member_type: MemberType::Admin,
+        recipient_mode: RecipientMode::Unrestricted,
+    });
+
+    let member_list = &mut ctx.accounts.member_list;
+    member_list.set_inner(MemberList {
+        smart_wallet: ctx.accounts.smart_wallet.key(),
+        bump: ctx.bumps.member_list,
+        ..Default::default()
+    });
+    member_list.add(member.owner, member.member_type)?;
+    member_list.add_token(args.token)?;
     Ok(())
 }
 
@@ -53,7 +65,15 @@ pub struct InitRule<'info> {
     pub smart_wallet: UncheckedAccount<'info>,
 
     #[account(
-        init_if_needed,
+        seeds = [args.passkey_pubkey.to_hashed_bytes(smart_wallet.key()).as_ref()],
+        bump,
+        seeds::program = lazorkit.key(), // LazorKit ID
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        init,
         payer = payer,
         space = 8 + Member::INIT_SPACE,
         seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
@@ -61,6 +81,15 @@ pub struct InitRule<'info> {
     )]
     pub member: Box<Account<'info, Member>>,
 
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MemberList::INIT_SPACE,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
     #[account(
         init,
         payer = payer,
@@ -77,15 +106,10 @@ pub struct InitRule<'info> {
     )]
     pub smart_wallet_config: Account<'info, SmartWalletConfig>,
 
-    #[account(
-        seeds = [args.passkey_pubkey.to_hashed_bytes(smart_wallet.key()).as_ref()],
-        bump,
-        seeds::program = lazorkit.key(), // LazorKit ID
-        signer,
-    )]
-    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
-
     pub lazorkit: Program<'info, Lazorkit>,
 
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Mint of `args.token`, read for its decimals. Omitted for SOL
+    pub mint: Option<UncheckedAccount<'info>>,
 }
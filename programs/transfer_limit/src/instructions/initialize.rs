@@ -1,9 +1,32 @@
-use crate::state::Config;
+use crate::{errors::TransferLimitError, program::TransferLimit, state::Config, ID};
 use anchor_lang::prelude::*;
+use lazorkit::utils::create_pda_account;
 
 pub fn initialize(ctx: Context<Initialize>, lazorkit_author: Pubkey) -> Result<()> {
-    let config = &mut ctx.accounts.config;
-    config.authority = lazorkit_author;
+    // Created here rather than with `init`, which would fail a second call with the system
+    // program's "already in use" error before this check could run
+    require!(
+        ctx.accounts.config.data_is_empty(),
+        TransferLimitError::AlreadyInitialized
+    );
+    require!(
+        lazorkit_author != Pubkey::default(),
+        TransferLimitError::InvalidAuthority
+    );
+
+    let config = &ctx.accounts.config;
+    create_pda_account(
+        &ctx.accounts.signer,
+        config,
+        &ctx.accounts.system_program,
+        8 + Config::INIT_SPACE,
+        &ID,
+        &[b"config", &[ctx.bumps.config]],
+    )?;
+    Config {
+        authority: lazorkit_author,
+    }
+    .try_serialize(&mut &mut config.try_borrow_mut_data()?[..])?;
 
     Ok(())
 }
@@ -14,13 +37,21 @@ pub struct Initialize<'info> {
     pub signer: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = signer,
-        space = 8 + Config::INIT_SPACE,
+        mut,
         seeds = [b"config"],
         bump,
     )]
-    pub config: Account<'info, Config>,
+    /// CHECK: Created by `initialize`, which fails if it already exists.
+    pub config: UncheckedAccount<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, TransferLimit>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key())
+            @ TransferLimitError::InvalidUpgradeAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
 
     pub system_program: Program<'info, System>,
 }
@@ -1,9 +1,27 @@
 mod add_member;
+mod add_recipient;
+mod add_token_limit;
 mod check_rule;
+mod destroy;
 mod init_rule;
 mod initialize;
+mod remove_member;
+mod remove_recipient;
+mod remove_token_limit;
+mod revoke_authenticator;
+mod update_member;
+mod update_token_limit;
 
 pub use add_member::*;
+pub use add_recipient::*;
+pub use add_token_limit::*;
 pub use check_rule::*;
+pub use destroy::*;
 pub use init_rule::*;
 pub use initialize::*;
+pub use remove_member::*;
+pub use remove_recipient::*;
+pub use remove_token_limit::*;
+pub use revoke_authenticator::*;
+pub use update_member::*;
+pub use update_token_limit::*;
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    instructions::close_records,
+    state::{Member, MemberList, MemberType},
+    ID,
+};
+
+/// Remove a member from the rule, closing its member account and its spend record of
+/// every token limit, passed as remaining accounts.
+///
+/// Only the rule forgets the member: its passkey stays a lazorkit authenticator of the
+/// wallet, which can no longer pass `check_rule`, until it is revoked through lazorkit's
+/// `RevokeAuthenticator` action.
+pub fn remove_member<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, RemoveMember<'info>>,
+) -> Result<()> {
+    let member = ctx.accounts.member.owner;
+    let member_list = &mut ctx.accounts.member_list;
+    member_list.remove(&member)?;
+    close_records(
+        ctx.remaining_accounts,
+        &ctx.accounts.smart_wallet,
+        member_list.tokens.iter().map(|token| (*token, member)),
+    )
+}
+
+#[derive(Accounts)]
+pub struct RemoveMember<'info> {
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        mut,
+        address = smart_wallet_authenticator.smart_wallet,
+    )]
+    /// CHECK: Receives the rent of the closed member account
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), member.owner.as_ref()],
+        bump,
+        owner = ID,
+        close = smart_wallet,
+    )]
+    pub member: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    state::{Member, MemberList, MemberType, Recipient},
+    ID,
+};
+
+pub fn remove_recipient(ctx: Context<RemoveRecipient>) -> Result<()> {
+    let member_list = &mut ctx.accounts.member_list;
+    member_list.recipient_count = member_list.recipient_count.saturating_sub(1);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveRecipient<'info> {
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        mut,
+        address = smart_wallet_authenticator.smart_wallet,
+    )]
+    /// CHECK: Receives the rent of the closed recipient account
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [Recipient::PREFIX_SEED, smart_wallet.key().as_ref(), recipient.address.as_ref()],
+        bump = recipient.bump,
+        owner = ID,
+        close = smart_wallet,
+    )]
+    pub recipient: Box<Account<'info, Recipient>>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    instructions::close_records,
+    state::{Member, MemberList, MemberType, RuleData},
+    ID,
+};
+
+/// Delete a token limit, after which members can no longer move that token.
+///
+/// The record of every member for the token must be passed as remaining accounts, and is
+/// closed, so that a limit added again later starts without stale spend.
+pub fn remove_token_limit<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, RemoveTokenLimit<'info>>,
+) -> Result<()> {
+    let token = ctx.accounts.rule_data.token;
+    let member_list = &mut ctx.accounts.member_list;
+    member_list.remove_token(token);
+    close_records(
+        ctx.remaining_accounts,
+        &ctx.accounts.smart_wallet,
+        member_list
+            .members
+            .iter()
+            .map(|entry| (token, entry.authenticator)),
+    )
+}
+
+#[derive(Accounts)]
+pub struct RemoveTokenLimit<'info> {
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        mut,
+        address = smart_wallet_authenticator.smart_wallet,
+    )]
+    /// CHECK: Receives the rent of the closed rule data account
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [RuleData::PREFIX_SEED, smart_wallet.key().as_ref(), rule_data.token.unwrap_or_default().as_ref()],
+        bump,
+        owner = ID,
+        close = smart_wallet,
+    )]
+    pub rule_data: Box<Account<'info, RuleData>>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    state::{Member, MemberList, MemberType, Record},
+    ID,
+};
+
+/// Close the revoked member account, along with its spend record of every token limit,
+/// passed as remaining accounts. A member already removed by an admin had its records
+/// closed then, and no longer needs any: its member account is left out and passed empty
+/// among the remaining accounts instead, proving that nothing is left behind.
+pub fn revoke_authenticator<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, RevokeAuthenticator<'info>>,
+) -> Result<()> {
+    let revoked_authenticator = ctx.accounts.revoked_authenticator.key();
+    if ctx.accounts.member.is_none() {
+        let key = Member::address(ctx.accounts.smart_wallet.key, &revoked_authenticator);
+        let account = ctx
+            .remaining_accounts
+            .iter()
+            .find(|account| *account.key == key)
+            .ok_or(TransferLimitError::InvalidAccountInput)?;
+        require!(
+            account.data_is_empty(),
+            TransferLimitError::InvalidAccountInput
+        );
+        return Ok(());
+    }
+
+    let member_list = &mut ctx.accounts.member_list;
+    member_list.remove(&revoked_authenticator)?;
+    close_records(
+        ctx.remaining_accounts,
+        &ctx.accounts.smart_wallet,
+        member_list
+            .tokens
+            .iter()
+            .map(|token| (*token, revoked_authenticator)),
+    )
+}
+
+/// Close the spend record of every `(token, member)` pair, refunding the smart wallet.
+///
+/// Each record must be passed in `accounts`; one that was never created is passed empty,
+/// proving that no spend of the member is left behind.
+pub(crate) fn close_records<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    smart_wallet: &AccountInfo<'info>,
+    records: impl IntoIterator<Item = (Option<Pubkey>, Pubkey)>,
+) -> Result<()> {
+    for (token, member) in records {
+        let key = Record::address(smart_wallet.key, &token, &member);
+        let account = accounts
+            .iter()
+            .find(|account| *account.key == key)
+            .ok_or(TransferLimitError::InvalidAccountInput)?;
+        if account.owner == &ID {
+            require!(account.is_writable, TransferLimitError::InvalidAccountInput);
+            Account::<Record>::try_from(account)?.close(smart_wallet.clone())?;
+        } else {
+            require!(
+                account.data_is_empty(),
+                TransferLimitError::InvalidAccountInput
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RevokeAuthenticator<'info> {
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        owner = lazorkit.key(),
+    )]
+    /// CHECK: Authenticator being revoked by lazorkit
+    pub revoked_authenticator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        address = smart_wallet_authenticator.smart_wallet,
+    )]
+    /// CHECK: Receives the rent of the closed member account
+    pub smart_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [Member::PREFIX_SEED, smart_wallet.key().as_ref(), revoked_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        close = smart_wallet,
+    )]
+    pub member: Option<Account<'info, Member>>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    state::{Member, MemberList, MemberType, RecipientMode},
+    ID,
+};
+
+pub fn promote_member(ctx: Context<UpdateMember>) -> Result<()> {
+    set_member_type(ctx, MemberType::Admin)
+}
+
+pub fn demote_member(ctx: Context<UpdateMember>) -> Result<()> {
+    set_member_type(ctx, MemberType::Member)
+}
+
+pub fn set_recipient_mode(ctx: Context<UpdateMember>, recipient_mode: RecipientMode) -> Result<()> {
+    ctx.accounts.member.recipient_mode = recipient_mode;
+    Ok(())
+}
+
+fn set_member_type(ctx: Context<UpdateMember>, member_type: MemberType) -> Result<()> {
+    let member = &mut ctx.accounts.member;
+    ctx.accounts
+        .member_list
+        .set_member_type(&member.owner, member_type)?;
+    member.member_type = member_type;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateMember<'info> {
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [Member::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), member.owner.as_ref()],
+        bump,
+        owner = ID,
+    )]
+    pub member: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [MemberList::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref()],
+        bump = member_list.bump,
+        owner = ID,
+    )]
+    pub member_list: Box<Account<'info, MemberList>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use lazorkit::{program::Lazorkit, state::SmartWalletAuthenticator};
+
+use crate::{
+    errors::TransferLimitError,
+    state::{Member, MemberType, RuleData},
+    ID,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdateTokenLimitArgs {
+    pub limit_amount: u64,
+    pub limit_period: u64,
+}
+
+pub fn update_token_limit(
+    ctx: Context<UpdateTokenLimit>,
+    args: UpdateTokenLimitArgs,
+) -> Result<()> {
+    ctx.accounts
+        .rule_data
+        .set_limit(args.limit_amount, args.limit_period)
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenLimit<'info> {
+    #[account(
+        owner = lazorkit.key(),
+        signer,
+    )]
+    pub smart_wallet_authenticator: Account<'info, SmartWalletAuthenticator>,
+
+    #[account(
+        seeds = [Member::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), smart_wallet_authenticator.key().as_ref()],
+        bump,
+        owner = ID,
+        constraint = admin.member_type == MemberType::Admin @ TransferLimitError::UnAuthorize,
+    )]
+    pub admin: Account<'info, Member>,
+
+    #[account(
+        mut,
+        seeds = [RuleData::PREFIX_SEED, smart_wallet_authenticator.smart_wallet.key().as_ref(), rule_data.token.unwrap_or_default().as_ref()],
+        bump,
+        owner = ID,
+    )]
+    pub rule_data: Box<Account<'info, RuleData>>,
+
+    pub lazorkit: Program<'info, Lazorkit>,
+}
@@ -1,7 +1,12 @@
+// Anchor's `#[program]` expansion still calls the deprecated `AccountInfo::realloc`.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+use rule_interface::{discriminator, CheckRuleArgs};
 
 mod errors;
 mod instructions;
+mod spend;
 mod state;
 
 use instructions::*;
@@ -16,6 +21,7 @@ pub mod transfer_limit {
         instructions::initialize(ctx, lazorkit_author)
     }
 
+    #[instruction(discriminator = &discriminator::INIT_RULE)]
     pub fn init_rule(ctx: Context<InitRule>, init_rule_args: InitRuleArgs) -> Result<()> {
         instructions::init_rule(ctx, init_rule_args)
     }
@@ -28,13 +34,69 @@ pub mod transfer_limit {
         instructions::add_member(ctx, new_passkey_pubkey, bump)
     }
 
-    pub fn check_rule(
-        ctx: Context<CheckRule>,
-        token: Option<Pubkey>,
-        cpi_data: Vec<u8>,
-        program_id: Pubkey,
+    pub fn remove_member<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, RemoveMember<'info>>,
+    ) -> Result<()> {
+        instructions::remove_member(ctx)
+    }
+
+    pub fn promote_member(ctx: Context<UpdateMember>) -> Result<()> {
+        instructions::promote_member(ctx)
+    }
+
+    pub fn demote_member(ctx: Context<UpdateMember>) -> Result<()> {
+        instructions::demote_member(ctx)
+    }
+
+    pub fn set_recipient_mode(
+        ctx: Context<UpdateMember>,
+        recipient_mode: state::RecipientMode,
+    ) -> Result<()> {
+        instructions::set_recipient_mode(ctx, recipient_mode)
+    }
+
+    pub fn add_token_limit(ctx: Context<AddTokenLimit>, args: AddTokenLimitArgs) -> Result<()> {
+        instructions::add_token_limit(ctx, args)
+    }
+
+    pub fn update_token_limit(
+        ctx: Context<UpdateTokenLimit>,
+        args: UpdateTokenLimitArgs,
+    ) -> Result<()> {
+        instructions::update_token_limit(ctx, args)
+    }
+
+    pub fn remove_token_limit<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, RemoveTokenLimit<'info>>,
+    ) -> Result<()> {
+        instructions::remove_token_limit(ctx)
+    }
+
+    pub fn add_recipient(ctx: Context<AddRecipient>, args: AddRecipientArgs) -> Result<()> {
+        instructions::add_recipient(ctx, args)
+    }
+
+    pub fn remove_recipient(ctx: Context<RemoveRecipient>) -> Result<()> {
+        instructions::remove_recipient(ctx)
+    }
+
+    #[instruction(discriminator = &discriminator::CHECK_RULE)]
+    pub fn check_rule(ctx: Context<CheckRule>, args: CheckRuleArgs) -> Result<()> {
+        instructions::check_rule(ctx, args)
+    }
+
+    #[instruction(discriminator = &discriminator::DESTROY)]
+    pub fn destroy<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, Destroy<'info>>,
+    ) -> Result<()> {
+        instructions::destroy(ctx)
+    }
+
+    #[instruction(discriminator = &discriminator::REVOKE_AUTHENTICATOR)]
+    pub fn revoke_authenticator<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, RevokeAuthenticator<'info>>,
     ) -> Result<()> {
-        instructions::check_rule(ctx, token, cpi_data, program_id)
+        instructions::revoke_authenticator(ctx)
     }
 
     // pub fn execute_instruction<'c: 'info, 'info>(
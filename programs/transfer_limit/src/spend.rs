@@ -0,0 +1,478 @@
+use anchor_lang::{prelude::*, system_program::ID as SYSTEM_ID};
+use lazorkit::constants::{
+    SOL_TRANSFER_DISCRIMINATOR, TOKEN_2022_PROGRAM_ID, TOKEN_ACCOUNT_SIZE, TOKEN_PROGRAM_ID,
+};
+use rule_interface::CheckRuleArgs;
+
+use crate::errors::TransferLimitError;
+
+/// Decimals of SOL, whose base unit is the lamport
+pub const NATIVE_DECIMALS: u8 = 9;
+
+/// Size of an SPL Token mint, which Token-2022 mints start with
+const MINT_SIZE: usize = 82;
+const MINT_DECIMALS_OFFSET: usize = 44;
+
+/// SPL Token instruction tags, shared by Token-2022
+mod token_instruction {
+    pub const TRANSFER: u8 = 3;
+    pub const APPROVE: u8 = 4;
+    pub const BURN: u8 = 8;
+    pub const TRANSFER_CHECKED: u8 = 12;
+    pub const APPROVE_CHECKED: u8 = 13;
+    pub const BURN_CHECKED: u8 = 15;
+}
+
+/// Where the mint of a spend is found
+#[derive(Debug, PartialEq, Eq)]
+pub enum MintSource {
+    /// Lamports moved by the System Program
+    Native,
+    /// Mint account passed to the token instruction
+    Mint(Pubkey),
+    /// Token account whose mint is spent, for instructions not naming the mint
+    TokenAccount(Pubkey),
+}
+
+/// Where the recipient of a spend is found
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecipientSource {
+    /// Account receiving lamports, or delegate of an approval
+    Address(Pubkey),
+    /// Token account receiving the tokens, the recipient being its owner
+    TokenAccount(Pubkey),
+}
+
+/// Value leaving the smart wallet through an instruction it is about to execute, in base
+/// units of its mint, or lamports for SOL
+#[derive(Debug, PartialEq, Eq)]
+pub struct Spend {
+    pub mint: MintSource,
+    pub amount: u64,
+    /// Decimals `amount` is expressed in, when the instruction states them: always for
+    /// SOL, and for the checked token instructions
+    pub decimals: Option<u8>,
+    /// `None` for burns, which have no recipient
+    pub recipient: Option<RecipientSource>,
+}
+
+impl Spend {
+    /// Decode a SOL transfer, or an SPL Token or Token-2022 transfer, approval or burn.
+    /// Returns `None` for any other instruction
+    pub fn decode(args: &CheckRuleArgs) -> Option<Self> {
+        let account = |index: usize| args.accounts.get(index).map(|meta| meta.pubkey);
+
+        if args.program_id == SYSTEM_ID {
+            if args.data.get(..4)? != SOL_TRANSFER_DISCRIMINATOR {
+                return None;
+            }
+            // [from, to]
+            return Some(Self {
+                mint: MintSource::Native,
+                amount: u64::from_le_bytes(args.data.get(4..12)?.try_into().ok()?),
+                decimals: Some(NATIVE_DECIMALS),
+                recipient: Some(RecipientSource::Address(account(1)?)),
+            });
+        }
+
+        if args.program_id != TOKEN_PROGRAM_ID && args.program_id != TOKEN_2022_PROGRAM_ID {
+            return None;
+        }
+        let amount = u64::from_le_bytes(args.data.get(1..9)?.try_into().ok()?);
+        let tag = *args.data.first()?;
+        let decimals = match tag {
+            token_instruction::TRANSFER_CHECKED
+            | token_instruction::APPROVE_CHECKED
+            | token_instruction::BURN_CHECKED => Some(*args.data.get(9)?),
+            _ => None,
+        };
+        let (mint, recipient) = match tag {
+            // [source, destination, authority]
+            token_instruction::TRANSFER => (
+                MintSource::TokenAccount(account(0)?),
+                Some(RecipientSource::TokenAccount(account(1)?)),
+            ),
+            // [source, delegate, owner]
+            token_instruction::APPROVE => (
+                MintSource::TokenAccount(account(0)?),
+                Some(RecipientSource::Address(account(1)?)),
+            ),
+            // [account, mint, authority]
+            token_instruction::BURN | token_instruction::BURN_CHECKED => {
+                (MintSource::Mint(account(1)?), None)
+            }
+            // [source, mint, destination, authority]
+            token_instruction::TRANSFER_CHECKED => (
+                MintSource::Mint(account(1)?),
+                Some(RecipientSource::TokenAccount(account(2)?)),
+            ),
+            // [source, mint, delegate, owner]
+            token_instruction::APPROVE_CHECKED => (
+                MintSource::Mint(account(1)?),
+                Some(RecipientSource::Address(account(2)?)),
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            mint,
+            amount,
+            decimals,
+            recipient,
+        })
+    }
+
+    /// Amount in base units of a mint with `decimals` decimals. Amounts in more precise
+    /// units are rounded up, so that a spend is never undercounted
+    pub fn normalized_amount(&self, decimals: u8) -> Result<u64> {
+        let from = self.decimals.unwrap_or(decimals);
+        let scale = |exponent: u8| {
+            10u64
+                .checked_pow(exponent as u32)
+                .ok_or(TransferLimitError::InvalidMint)
+        };
+        let amount = if from <= decimals {
+            self.amount.checked_mul(scale(decimals - from)?)
+        } else {
+            Some(self.amount.div_ceil(scale(from - decimals)?))
+        };
+        Ok(amount.ok_or(TransferLimitError::TransferAmountExceedLimit)?)
+    }
+
+    /// Resolve the spent mint, `None` for SOL. Token accounts are looked up in `accounts`
+    /// and must be owned by `token_program`
+    pub fn resolve_mint(
+        &self,
+        accounts: &[AccountInfo],
+        token_program: &Pubkey,
+    ) -> Result<Option<Pubkey>> {
+        match self.mint {
+            MintSource::Native => Ok(None),
+            MintSource::Mint(mint) => Ok(Some(mint)),
+            MintSource::TokenAccount(key) => {
+                token_account_field(accounts, &key, token_program, 0).map(Some)
+            }
+        }
+    }
+
+    /// Resolve the recipient, `None` for burns. Token accounts are looked up in `accounts`
+    /// and must be owned by `token_program`
+    pub fn resolve_recipient(
+        &self,
+        accounts: &[AccountInfo],
+        token_program: &Pubkey,
+    ) -> Result<Option<Pubkey>> {
+        match self.recipient {
+            None => Ok(None),
+            Some(RecipientSource::Address(address)) => Ok(Some(address)),
+            Some(RecipientSource::TokenAccount(key)) => {
+                token_account_field(accounts, &key, token_program, 32).map(Some)
+            }
+        }
+    }
+}
+
+/// Decimals of `token`, read from its `mint` account; SOL has no mint
+pub fn token_decimals(token: Option<Pubkey>, mint: Option<&AccountInfo>) -> Result<u8> {
+    let Some(token) = token else {
+        return Ok(NATIVE_DECIMALS);
+    };
+    let mint = mint.ok_or(TransferLimitError::InvalidMint)?;
+    require!(
+        *mint.key == token
+            && (*mint.owner == TOKEN_PROGRAM_ID || *mint.owner == TOKEN_2022_PROGRAM_ID),
+        TransferLimitError::InvalidMint
+    );
+    let data = mint.try_borrow_data()?;
+    require!(data.len() >= MINT_SIZE, TransferLimitError::InvalidMint);
+    Ok(data[MINT_DECIMALS_OFFSET])
+}
+
+/// Key stored at `offset` of token account `key`: its mint at 0, its owner at 32
+fn token_account_field(
+    accounts: &[AccountInfo],
+    key: &Pubkey,
+    token_program: &Pubkey,
+    offset: usize,
+) -> Result<Pubkey> {
+    let account = accounts
+        .iter()
+        .find(|account| account.key == key)
+        .ok_or(TransferLimitError::InvalidTokenAccount)?;
+    require!(
+        account.owner == token_program,
+        TransferLimitError::InvalidTokenAccount
+    );
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() >= TOKEN_ACCOUNT_SIZE,
+        TransferLimitError::InvalidTokenAccount
+    );
+    Ok(Pubkey::new_from_array(
+        data[offset..offset + 32].try_into().unwrap(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rule_interface::RuleAccountMeta;
+
+    fn args(program_id: Pubkey, data: Vec<u8>, accounts: &[Pubkey]) -> CheckRuleArgs {
+        CheckRuleArgs {
+            program_id,
+            accounts: accounts
+                .iter()
+                .map(|pubkey| RuleAccountMeta {
+                    pubkey: *pubkey,
+                    is_signer: false,
+                    is_writable: true,
+                })
+                .collect(),
+            data,
+        }
+    }
+
+    fn token_data(tag: u8, amount: u64, decimals: Option<u8>) -> Vec<u8> {
+        let mut data = vec![tag];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend(decimals);
+        data
+    }
+
+    #[test]
+    fn decodes_sol_transfers() {
+        let mut data = SOL_TRANSFER_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&500u64.to_le_bytes());
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        assert_eq!(
+            Spend::decode(&args(SYSTEM_ID, data.clone(), &keys)),
+            Some(Spend {
+                mint: MintSource::Native,
+                amount: 500,
+                decimals: Some(NATIVE_DECIMALS),
+                recipient: Some(RecipientSource::Address(keys[1])),
+            })
+        );
+        assert_eq!(
+            Spend::decode(&args(SYSTEM_ID, data[..8].to_vec(), &keys)),
+            None
+        );
+        // Allocate shares the System Program but moves nothing
+        assert_eq!(
+            Spend::decode(&args(
+                SYSTEM_ID,
+                vec![8, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0],
+                &keys
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn decodes_token_instructions_of_both_programs() {
+        let (source, mint, to, other) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        for program_id in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            for (data, keys, mint_source, recipient) in [
+                (
+                    token_data(token_instruction::TRANSFER, 7, None),
+                    vec![source, to, other],
+                    MintSource::TokenAccount(source),
+                    Some(RecipientSource::TokenAccount(to)),
+                ),
+                (
+                    token_data(token_instruction::APPROVE, 7, None),
+                    vec![source, to, other],
+                    MintSource::TokenAccount(source),
+                    Some(RecipientSource::Address(to)),
+                ),
+                (
+                    token_data(token_instruction::BURN, 7, None),
+                    vec![source, mint, other],
+                    MintSource::Mint(mint),
+                    None,
+                ),
+                (
+                    token_data(token_instruction::TRANSFER_CHECKED, 7, Some(6)),
+                    vec![source, mint, to, other],
+                    MintSource::Mint(mint),
+                    Some(RecipientSource::TokenAccount(to)),
+                ),
+                (
+                    token_data(token_instruction::APPROVE_CHECKED, 7, Some(6)),
+                    vec![source, mint, to, other],
+                    MintSource::Mint(mint),
+                    Some(RecipientSource::Address(to)),
+                ),
+                (
+                    token_data(token_instruction::BURN_CHECKED, 7, Some(6)),
+                    vec![source, mint, other],
+                    MintSource::Mint(mint),
+                    None,
+                ),
+            ] {
+                assert_eq!(
+                    Spend::decode(&args(program_id, data.clone(), &keys)),
+                    Some(Spend {
+                        mint: mint_source,
+                        amount: 7,
+                        decimals: data.get(9).copied(),
+                        recipient,
+                    })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_other_or_malformed_instructions() {
+        let keys = [Pubkey::new_unique(); 4];
+        // MintTo
+        assert_eq!(
+            Spend::decode(&args(TOKEN_PROGRAM_ID, token_data(7, 7, None), &keys)),
+            None
+        );
+        assert_eq!(
+            Spend::decode(&args(
+                TOKEN_PROGRAM_ID,
+                token_data(token_instruction::TRANSFER, 7, None)[..5].to_vec(),
+                &keys
+            )),
+            None
+        );
+        assert_eq!(
+            Spend::decode(&args(
+                TOKEN_PROGRAM_ID,
+                token_data(token_instruction::BURN, 7, None),
+                &keys[..1]
+            )),
+            None
+        );
+        assert_eq!(
+            Spend::decode(&args(
+                Pubkey::new_unique(),
+                token_data(token_instruction::TRANSFER, 7, None),
+                &keys
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn resolves_the_mint_and_owner_of_token_accounts() {
+        let (key, mint, owner, program) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            TOKEN_PROGRAM_ID,
+        );
+        let mut data = vec![0; TOKEN_ACCOUNT_SIZE];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program,
+            false,
+            0,
+        );
+
+        let spend = Spend {
+            mint: MintSource::TokenAccount(key),
+            amount: 1,
+            decimals: None,
+            recipient: Some(RecipientSource::TokenAccount(key)),
+        };
+        assert_eq!(
+            spend
+                .resolve_mint(std::slice::from_ref(&account), &program)
+                .unwrap(),
+            Some(mint)
+        );
+        assert!(spend
+            .resolve_mint(std::slice::from_ref(&account), &TOKEN_2022_PROGRAM_ID)
+            .is_err());
+        assert!(spend.resolve_mint(&[], &program).is_err());
+        assert_eq!(
+            spend
+                .resolve_recipient(std::slice::from_ref(&account), &program)
+                .unwrap(),
+            Some(owner)
+        );
+    }
+
+    fn spend(amount: u64, decimals: Option<u8>) -> Spend {
+        Spend {
+            mint: MintSource::Native,
+            amount,
+            decimals,
+            recipient: None,
+        }
+    }
+
+    #[test]
+    fn normalizes_amounts_to_the_rule_decimals() {
+        // Unchecked instructions are in the mint's own base units
+        assert_eq!(spend(1_234, None).normalized_amount(6).unwrap(), 1_234);
+        assert_eq!(spend(1_234, Some(6)).normalized_amount(6).unwrap(), 1_234);
+        assert_eq!(spend(12, Some(2)).normalized_amount(6).unwrap(), 120_000);
+        assert_eq!(spend(120_000, Some(6)).normalized_amount(2).unwrap(), 12);
+    }
+
+    #[test]
+    fn normalization_never_undercounts_a_spend() {
+        assert_eq!(spend(120_001, Some(6)).normalized_amount(2).unwrap(), 13);
+        assert_eq!(spend(1, Some(9)).normalized_amount(0).unwrap(), 1);
+        assert!(spend(u64::MAX, Some(0)).normalized_amount(1).is_err());
+        assert!(spend(1, Some(0)).normalized_amount(30).is_err());
+    }
+
+    #[test]
+    fn reads_decimals_from_the_mint_of_the_token() {
+        let (key, program) = (Pubkey::new_unique(), TOKEN_2022_PROGRAM_ID);
+        let mut data = vec![0; MINT_SIZE + 10];
+        data[MINT_DECIMALS_OFFSET] = 6;
+        let mut lamports = 0;
+        let mint = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program,
+            false,
+            0,
+        );
+
+        assert_eq!(token_decimals(None, None).unwrap(), NATIVE_DECIMALS);
+        assert_eq!(token_decimals(Some(key), Some(&mint)).unwrap(), 6);
+        assert!(token_decimals(Some(key), None).is_err());
+        assert!(token_decimals(Some(Pubkey::new_unique()), Some(&mint)).is_err());
+    }
+
+    #[test]
+    fn rejects_mints_of_other_programs() {
+        let (key, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0; MINT_SIZE];
+        let mut lamports = 0;
+        let mint = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program,
+            false,
+            0,
+        );
+        assert!(token_decimals(Some(key), Some(&mint)).is_err());
+    }
+}
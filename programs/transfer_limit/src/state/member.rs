@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
 
-#[derive(Default, InitSpace, Clone, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+use super::RecipientMode;
+use crate::errors::TransferLimitError;
+
+#[derive(
+    Default, InitSpace, Clone, Copy, Debug, AnchorSerialize, AnchorDeserialize, PartialEq, Eq,
+)]
 pub enum MemberType {
     Admin,
     #[default]
@@ -15,8 +20,190 @@ pub struct Member {
     pub smart_wallet: Pubkey,
     pub bump: u8,
     pub is_initialized: bool,
+    pub recipient_mode: RecipientMode,
 }
 
 impl Member {
     pub const PREFIX_SEED: &'static [u8] = b"member";
+
+    pub fn address(smart_wallet: &Pubkey, authenticator: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                Self::PREFIX_SEED,
+                smart_wallet.as_ref(),
+                authenticator.as_ref(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+}
+
+/// Entry of a wallet's member list
+#[derive(InitSpace, Clone, Copy, Debug, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct MemberEntry {
+    pub authenticator: Pubkey,
+    pub member_type: MemberType,
+}
+
+/// Every member of a smart wallet, kept in step with its member accounts, along with the
+/// wallet's token limits and the size of its address book, so that `destroy` can require
+/// every account of the wallet to be closed.
+///
+/// Clients list a wallet's members by fetching this account; the full list does not fit
+/// in the 1024 bytes of return data an instruction could hand back.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct MemberList {
+    pub smart_wallet: Pubkey,
+    #[max_len(32)]
+    pub members: Vec<MemberEntry>,
+    /// Token of every rule data account of the wallet, `None` for SOL
+    #[max_len(8)]
+    pub tokens: Vec<Option<Pubkey>>,
+    /// Number of recipient accounts in the wallet's address book
+    pub recipient_count: u16,
+    pub bump: u8,
+}
+
+impl MemberList {
+    pub const PREFIX_SEED: &'static [u8] = b"member_list";
+    pub const MAX_MEMBERS: usize = 32;
+    pub const MAX_TOKEN_LIMITS: usize = 8;
+
+    pub fn admin_count(&self) -> usize {
+        self.members
+            .iter()
+            .filter(|entry| entry.member_type == MemberType::Admin)
+            .count()
+    }
+
+    /// Add `authenticator` to the list, doing nothing if it is listed already
+    pub fn add(&mut self, authenticator: Pubkey, member_type: MemberType) -> Result<()> {
+        if self.position(&authenticator).is_ok() {
+            return Ok(());
+        }
+        require!(
+            self.members.len() < Self::MAX_MEMBERS,
+            TransferLimitError::TooManyMembers
+        );
+        self.members.push(MemberEntry {
+            authenticator,
+            member_type,
+        });
+        Ok(())
+    }
+
+    /// Remove `authenticator` from the list, refusing to remove the last admin
+    pub fn remove(&mut self, authenticator: &Pubkey) -> Result<()> {
+        let index = self.position(authenticator)?;
+        self.require_not_last_admin(index)?;
+        self.members.remove(index);
+        Ok(())
+    }
+
+    /// Change the role of `authenticator`, refusing to demote the last admin
+    pub fn set_member_type(
+        &mut self,
+        authenticator: &Pubkey,
+        member_type: MemberType,
+    ) -> Result<()> {
+        let index = self.position(authenticator)?;
+        if member_type != MemberType::Admin {
+            self.require_not_last_admin(index)?;
+        }
+        self.members[index].member_type = member_type;
+        Ok(())
+    }
+
+    /// Record a new token limit of the wallet
+    pub fn add_token(&mut self, token: Option<Pubkey>) -> Result<()> {
+        require!(
+            self.tokens.len() < Self::MAX_TOKEN_LIMITS,
+            TransferLimitError::TooManyTokenLimits
+        );
+        if !self.tokens.contains(&token) {
+            self.tokens.push(token);
+        }
+        Ok(())
+    }
+
+    /// Forget a removed token limit of the wallet
+    pub fn remove_token(&mut self, token: Option<Pubkey>) {
+        self.tokens.retain(|listed| *listed != token);
+    }
+
+    fn position(&self, authenticator: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|entry| entry.authenticator == *authenticator)
+            .ok_or(TransferLimitError::MemberNotFound.into())
+    }
+
+    fn require_not_last_admin(&self, index: usize) -> Result<()> {
+        require!(
+            self.members[index].member_type != MemberType::Admin || self.admin_count() > 1,
+            TransferLimitError::LastAdmin
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(members: &[(Pubkey, MemberType)]) -> MemberList {
+        let mut list = MemberList::default();
+        for (authenticator, member_type) in members {
+            list.add(*authenticator, *member_type).unwrap();
+        }
+        list
+    }
+
+    #[test]
+    fn add_ignores_listed_members_and_caps_the_list() {
+        let admin = Pubkey::new_unique();
+        let mut list = list(&[(admin, MemberType::Admin)]);
+        list.add(admin, MemberType::Member).unwrap();
+        assert_eq!(list.members.len(), 1);
+        assert_eq!(list.members[0].member_type, MemberType::Admin);
+
+        while list.members.len() < MemberList::MAX_MEMBERS {
+            list.add(Pubkey::new_unique(), MemberType::Member).unwrap();
+        }
+        assert!(list.add(Pubkey::new_unique(), MemberType::Member).is_err());
+    }
+
+    #[test]
+    fn last_admin_cannot_be_removed_or_demoted() {
+        let (admin, member) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut list = list(&[(admin, MemberType::Admin), (member, MemberType::Member)]);
+        assert!(list.remove(&admin).is_err());
+        assert!(list.set_member_type(&admin, MemberType::Member).is_err());
+
+        list.set_member_type(&member, MemberType::Admin).unwrap();
+        list.set_member_type(&admin, MemberType::Member).unwrap();
+        assert!(list.remove(&member).is_err());
+        list.remove(&admin).unwrap();
+        assert_eq!(list.admin_count(), 1);
+        assert!(list.remove(&admin).is_err());
+    }
+
+    #[test]
+    fn tokens_are_listed_once_and_capped() {
+        let mut list = MemberList::default();
+        list.add_token(None).unwrap();
+        list.add_token(None).unwrap();
+        assert_eq!(list.tokens, vec![None]);
+
+        while list.tokens.len() < MemberList::MAX_TOKEN_LIMITS {
+            list.add_token(Some(Pubkey::new_unique())).unwrap();
+        }
+        assert!(list.add_token(Some(Pubkey::new_unique())).is_err());
+
+        list.remove_token(None);
+        assert_eq!(list.tokens.len(), MemberList::MAX_TOKEN_LIMITS - 1);
+        assert!(!list.tokens.contains(&None));
+    }
 }
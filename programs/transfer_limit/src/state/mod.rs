@@ -1,8 +1,11 @@
 mod config;
 mod member;
+mod recipient;
 mod record;
 mod rule;
 
 pub use config::*;
 pub use member::*;
+pub use recipient::*;
+pub use record::*;
 pub use rule::*;
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+#[derive(InitSpace, Clone, Copy, Debug, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub enum RecipientKind {
+    Allowed,
+    Denied,
+}
+
+/// Destinations a member may send to
+#[derive(
+    Default, InitSpace, Clone, Copy, Debug, AnchorSerialize, AnchorDeserialize, PartialEq, Eq,
+)]
+pub enum RecipientMode {
+    #[default]
+    Unrestricted,
+    /// Only recipients in the wallet's address book as allowed
+    AllowlistOnly,
+    /// Anything but recipients in the wallet's address book as denied
+    ExceptDenylisted,
+}
+
+impl RecipientMode {
+    /// Whether a recipient listed as `kind` in the address book, if at all, may be sent to
+    pub fn permits(self, kind: Option<RecipientKind>) -> bool {
+        match self {
+            Self::Unrestricted => true,
+            Self::AllowlistOnly => kind == Some(RecipientKind::Allowed),
+            Self::ExceptDenylisted => kind != Some(RecipientKind::Denied),
+        }
+    }
+}
+
+/// Labeled destination in a smart wallet's address book
+#[account]
+#[derive(InitSpace)]
+pub struct Recipient {
+    pub smart_wallet: Pubkey,
+    pub address: Pubkey,
+    #[max_len(32)]
+    pub label: String,
+    pub kind: RecipientKind,
+    pub bump: u8,
+}
+
+impl Recipient {
+    pub const PREFIX_SEED: &'static [u8] = b"recipient";
+    pub const MAX_LABEL_LEN: usize = 32;
+
+    pub fn address(smart_wallet: &Pubkey, recipient: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[Self::PREFIX_SEED, smart_wallet.as_ref(), recipient.as_ref()],
+            &crate::ID,
+        )
+        .0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_permit_recipients_by_address_book_entry() {
+        let listings = [
+            None,
+            Some(RecipientKind::Allowed),
+            Some(RecipientKind::Denied),
+        ];
+        let permitted = |mode: RecipientMode| listings.map(|kind| mode.permits(kind));
+        assert_eq!(permitted(RecipientMode::Unrestricted), [true, true, true]);
+        assert_eq!(
+            permitted(RecipientMode::AllowlistOnly),
+            [false, true, false]
+        );
+        assert_eq!(
+            permitted(RecipientMode::ExceptDenylisted),
+            [true, true, false]
+        );
+    }
+}
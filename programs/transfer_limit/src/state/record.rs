@@ -1,5 +1,193 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::TransferLimitError;
+
+/// Amounts a member spent of a token over the last limit period, kept in
+/// [`Record::BUCKETS`] time buckets so the period rolls instead of resetting at once
 #[account]
-#[derive(Debug)]
-pub struct Record {}
+#[derive(Debug, Default, InitSpace)]
+pub struct Record {
+    pub smart_wallet: Pubkey,
+    pub token: Option<Pubkey>,
+    pub member: Pubkey,
+    /// Spent per bucket, indexed by bucket number modulo [`Record::BUCKETS`]
+    pub buckets: [u64; Record::BUCKETS],
+    /// Seconds covered by a bucket, derived from the limit period
+    pub bucket_width: u64,
+    /// Number of the bucket the last spend fell in, `unix_timestamp / bucket_width`
+    pub last_bucket: i64,
+    pub bump: u8,
+}
+
+impl Record {
+    pub const PREFIX_SEED: &'static [u8] = b"record";
+    pub const BUCKETS: usize = 24;
+
+    pub fn address(smart_wallet: &Pubkey, token: &Option<Pubkey>, member: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                Self::PREFIX_SEED,
+                smart_wallet.as_ref(),
+                token.unwrap_or_default().as_ref(),
+                member.as_ref(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    /// Bucket width for `limit_period`. Every bucket but the current one lies wholly in
+    /// the past, so `BUCKETS - 1` of them must span the period for no spend made within
+    /// it to drop out of the window early
+    fn bucket_width(limit_period: u64) -> u64 {
+        limit_period.div_ceil(Self::BUCKETS as u64 - 1).max(1)
+    }
+
+    /// Total spent in the window
+    pub fn spent(&self) -> u64 {
+        self.buckets
+            .iter()
+            .fold(0u64, |total, spent| total.saturating_add(*spent))
+    }
+
+    /// Add `amount` to the spend of the last `limit_period` seconds. A spend stays counted
+    /// for at least `limit_period` seconds and at most one bucket width longer
+    pub fn spend(
+        &mut self,
+        amount: u64,
+        limit_amount: u64,
+        limit_period: u64,
+        now: i64,
+    ) -> Result<()> {
+        self.roll(Self::bucket_width(limit_period), now);
+
+        let spent = self
+            .spent()
+            .checked_add(amount)
+            .ok_or(TransferLimitError::TransferAmountExceedLimit)?;
+        require!(
+            spent <= limit_amount,
+            TransferLimitError::TransferAmountExceedLimit
+        );
+        let bucket = &mut self.buckets[Self::index(self.last_bucket)];
+        *bucket = bucket.saturating_add(amount);
+        Ok(())
+    }
+
+    /// Move the window to the bucket `now` falls in, clearing the buckets it leaves
+    /// behind. A changed limit period moves everything still counted into the current
+    /// bucket, keeping it counted for a whole new period rather than dropping any of it
+    fn roll(&mut self, bucket_width: u64, now: i64) {
+        let current = now.div_euclid(bucket_width as i64);
+        if bucket_width != self.bucket_width {
+            let spent = self.spent();
+            self.buckets = [0; Self::BUCKETS];
+            self.buckets[Self::index(current)] = spent;
+            self.bucket_width = bucket_width;
+            self.last_bucket = current;
+            return;
+        }
+
+        // A clock running backwards keeps the window where it is
+        let elapsed = current.saturating_sub(self.last_bucket);
+        if elapsed <= 0 {
+            return;
+        }
+        if elapsed >= Self::BUCKETS as i64 {
+            self.buckets = [0; Self::BUCKETS];
+        } else {
+            for bucket in self.last_bucket + 1..=current {
+                self.buckets[Self::index(bucket)] = 0;
+            }
+        }
+        self.last_bucket = current;
+    }
+
+    fn index(bucket: i64) -> usize {
+        bucket.rem_euclid(Self::BUCKETS as i64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+    /// Start of a bucket, so `HOUR` later is the start of the next one
+    const START: i64 = 1_000 * HOUR;
+    const HOUR: i64 = (DAY / 23) as i64 + 1;
+
+    #[test]
+    fn cumulative_spend_is_capped_within_a_period() {
+        let mut record = Record::default();
+        record.spend(60, 100, DAY, START).unwrap();
+        record.spend(40, 100, DAY, START + 1_000).unwrap();
+        assert!(record.spend(1, 100, DAY, START + 2_000).is_err());
+        assert_eq!(record.spent(), 100);
+    }
+
+    #[test]
+    fn spends_leave_the_window_one_by_one() {
+        let mut record = Record::default();
+        record.spend(60, 100, DAY, START).unwrap();
+        record.spend(40, 100, DAY, START + 12 * HOUR).unwrap();
+
+        // A fixed period starting at the first spend would have reset here
+        assert!(record.spend(1, 100, DAY, START + DAY as i64).is_err());
+        // The first spend leaves with its bucket, the second stays
+        assert!(record.spend(1, 100, DAY, START + 23 * HOUR).is_err());
+        record.spend(60, 100, DAY, START + 24 * HOUR).unwrap();
+        assert!(record.spend(1, 100, DAY, START + 24 * HOUR).is_err());
+        assert_eq!(record.spent(), 100);
+
+        // Both are gone once the window has moved past them
+        record.spend(100, 100, DAY, START + 48 * HOUR).unwrap();
+        assert_eq!(record.spent(), 100);
+    }
+
+    #[test]
+    fn spend_stays_counted_up_to_the_window_boundary() {
+        let mut record = Record::default();
+        let spent_at = START + HOUR - 1;
+        record.spend(100, 100, DAY, spent_at).unwrap();
+
+        // Still within a period of the spend
+        assert!(record
+            .spend(1, 100, DAY, spent_at + DAY as i64 - 1)
+            .is_err());
+        assert!(record.spend(1, 100, DAY, spent_at + DAY as i64).is_err());
+        // Counted at most one bucket longer than the period
+        assert!(record.spend(1, 100, DAY, START + 23 * HOUR).is_err());
+        record.spend(1, 100, DAY, START + 24 * HOUR).unwrap();
+        assert_eq!(record.spent(), 1);
+    }
+
+    #[test]
+    fn changing_the_period_keeps_the_spend_counted() {
+        let mut record = Record::default();
+        record.spend(100, 100, DAY, START).unwrap();
+
+        let now = START + 10 * HOUR;
+        assert!(record.spend(1, 100, 2 * DAY, now).is_err());
+        assert!(record
+            .spend(1, 100, 2 * DAY, now + 2 * DAY as i64 - 1)
+            .is_err());
+        assert!(record.spend(1, 100, DAY, now + DAY as i64 - 1).is_err());
+    }
+
+    #[test]
+    fn clock_running_backwards_keeps_the_window() {
+        let mut record = Record::default();
+        record.spend(100, 100, DAY, START + 5 * HOUR).unwrap();
+        assert!(record.spend(1, 100, DAY, START).is_err());
+        assert!(record.spend(1, 100, DAY, START + 27 * HOUR).is_err());
+    }
+
+    #[test]
+    fn single_transfer_over_the_limit_is_rejected() {
+        let mut record = Record::default();
+        assert!(record.spend(101, 100, DAY, START).is_err());
+        assert!(record.spend(u64::MAX, u64::MAX, DAY, START).is_ok());
+        assert!(record.spend(1, u64::MAX, DAY, START).is_err());
+    }
+}
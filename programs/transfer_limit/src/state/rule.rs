@@ -1,14 +1,29 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::TransferLimitError;
+
 #[account]
 #[derive(Default, InitSpace)]
 pub struct RuleData {
     pub token: Option<Pubkey>,
+    /// Decimals of `token`, 9 for SOL
+    pub decimals: u8,
+    /// Amount that may be spent every period, in base units of `token` (lamports for SOL)
     pub limit_amount: u64,
+    /// Length in seconds of the period `limit_amount` applies to
+    pub limit_period: u64,
     pub bump: u8,
     pub is_initialized: bool,
 }
 
 impl RuleData {
     pub const PREFIX_SEED: &'static [u8] = b"rule_data";
+
+    /// Limit spends to `limit_amount` within any `limit_period` seconds
+    pub fn set_limit(&mut self, limit_amount: u64, limit_period: u64) -> Result<()> {
+        require!(limit_period > 0, TransferLimitError::InvalidLimitPeriod);
+        self.limit_amount = limit_amount;
+        self.limit_period = limit_period;
+        Ok(())
+    }
 }
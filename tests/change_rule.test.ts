@@ -3,6 +3,7 @@ import ECDSA from "ecdsa-secp256r1";
 import {
   Keypair,
   LAMPORTS_PER_SOL,
+  PublicKey,
   sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import * as dotenv from "dotenv";
@@ -12,6 +13,8 @@ import { DefaultRuleProgram } from "../sdk/default-rule-program";
 
 import { ExecuteAction } from "../sdk/types";
 import { TransferLimitProgram } from "../sdk/transfer_limit";
+import { TEST_RELYING_PARTY } from "./constants";
+import { passkeySigner, programDataAddress } from "./utils";
 dotenv.config();
 
 describe("Test smart wallet with transfer limit", () => {
@@ -30,6 +33,8 @@ describe("Test smart wallet with transfer limit", () => {
     bs58.decode(process.env.PRIVATE_KEY!)
   );
 
+  let treasury: PublicKey;
+
   before(async () => {
     const smartWalletSeqAccountInfo = await connection.getAccountInfo(
       lazorkitProgram.smartWalletSeq
@@ -38,7 +43,9 @@ describe("Test smart wallet with transfer limit", () => {
     if (smartWalletSeqAccountInfo === null) {
       const txn = await lazorkitProgram.initializeTxn(
         payer.publicKey,
-        defaultRuleProgram.programId
+        defaultRuleProgram.programId,
+        [TEST_RELYING_PARTY],
+        programDataAddress(lazorkitProgram.programId)
       );
 
       await sendAndConfirmTransaction(connection, txn, [payer], {
@@ -46,6 +53,12 @@ describe("Test smart wallet with transfer limit", () => {
       });
     }
 
+    const lazorkitConfigData =
+      await lazorkitProgram.program.account.config.fetch(
+        lazorkitProgram.config
+      );
+    treasury = lazorkitConfigData.treasury;
+
     const defaultRuleConfigAccountInfo = await connection.getAccountInfo(
       defaultRuleProgram.config
     );
@@ -54,7 +67,8 @@ describe("Test smart wallet with transfer limit", () => {
       // create the default rule program
       const txn = await defaultRuleProgram.initializeTxn(
         payer.publicKey,
-        lazorkitProgram.authority
+        lazorkitProgram.authority,
+        programDataAddress(defaultRuleProgram.programId)
       );
 
       await sendAndConfirmTransaction(connection, txn, [payer], {
@@ -70,7 +84,8 @@ describe("Test smart wallet with transfer limit", () => {
       // create the transfer limit program
       const txn = await transferLimitProgram.initializeTxn(
         payer.publicKey,
-        lazorkitProgram.authority
+        lazorkitProgram.authority,
+        programDataAddress(transferLimitProgram.programId)
       );
 
       await sendAndConfirmTransaction(connection, txn, [payer], {
@@ -141,7 +156,8 @@ describe("Test smart wallet with transfer limit", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -176,17 +192,14 @@ describe("Test smart wallet with transfer limit", () => {
       }
     );
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       destroyRuleDefaultIns,
       initTransferLimitRule,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ChangeProgramRule
     );
 
@@ -243,7 +256,8 @@ describe("Test smart wallet with transfer limit", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -278,17 +292,14 @@ describe("Test smart wallet with transfer limit", () => {
       }
     );
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       destroyRuleDefaultIns,
       initTransferLimitRule,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ChangeProgramRule
     );
 
@@ -319,20 +330,14 @@ describe("Test smart wallet with transfer limit", () => {
       bump
     );
 
-    const newMessage = Buffer.from("add member");
-    const newSignatureBytes = Buffer.from(
-      privateKey.sign(newMessage),
-      "base64"
-    );
-
     const addMemberTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      newMessage,
-      newSignatureBytes,
+      passkeySigner(privateKey),
       addMemberIns,
       null,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.CallRuleProgram,
       newPasskeyPubkey
     );
@@ -391,7 +396,8 @@ describe("Test smart wallet with transfer limit", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -426,17 +432,14 @@ describe("Test smart wallet with transfer limit", () => {
       }
     );
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       destroyRuleDefaultIns,
       initTransferLimitRule,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ChangeProgramRule
     );
 
@@ -465,12 +468,12 @@ describe("Test smart wallet with transfer limit", () => {
 
     const executeTxn2 = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       checkRuleIns,
       transferSolIns,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ExecuteCpi
     );
 
@@ -527,7 +530,8 @@ describe("Test smart wallet with transfer limit", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -562,17 +566,14 @@ describe("Test smart wallet with transfer limit", () => {
       }
     );
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       destroyRuleDefaultIns,
       initTransferLimitRule,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ChangeProgramRule
     );
 
@@ -608,12 +609,12 @@ describe("Test smart wallet with transfer limit", () => {
 
     const addMemberTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       addMemberIns,
       null,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.CallRuleProgram,
       newPasskeyPubkey
     );
@@ -642,19 +643,14 @@ describe("Test smart wallet with transfer limit", () => {
       transferSolIns
     );
 
-    const memberSignatureBytes = Buffer.from(
-      newPasskey.sign(message),
-      "base64"
-    );
-
     const executeTxn2 = await lazorkitProgram.executeInstructionTxn(
       newPasskeyPubkey,
-      message,
-      memberSignatureBytes,
+      passkeySigner(newPasskey),
       checkRuleIns,
       transferSolIns,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ExecuteCpi
     );
 
@@ -711,7 +707,8 @@ describe("Test smart wallet with transfer limit", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -746,17 +743,14 @@ describe("Test smart wallet with transfer limit", () => {
       }
     );
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       destroyRuleDefaultIns,
       initTransferLimitRule,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ChangeProgramRule
     );
 
@@ -792,12 +786,12 @@ describe("Test smart wallet with transfer limit", () => {
 
     const addMemberTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       addMemberIns,
       null,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.CallRuleProgram,
       newPasskeyPubkey
     );
@@ -826,19 +820,14 @@ describe("Test smart wallet with transfer limit", () => {
       transferSolIns
     );
 
-    const memberSignatureBytes = Buffer.from(
-      newPasskey.sign(message),
-      "base64"
-    );
-
     const executeTxn2 = await lazorkitProgram.executeInstructionTxn(
       newPasskeyPubkey,
-      message,
-      memberSignatureBytes,
+      passkeySigner(newPasskey),
       checkRuleIns,
       transferSolIns,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ExecuteCpi
     );
 
@@ -895,7 +884,8 @@ describe("Test smart wallet with transfer limit", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -930,17 +920,14 @@ describe("Test smart wallet with transfer limit", () => {
       }
     );
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       destroyRuleDefaultIns,
       initTransferLimitRule,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ChangeProgramRule
     );
 
@@ -976,12 +963,12 @@ describe("Test smart wallet with transfer limit", () => {
 
     const addMemberTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       addMemberIns,
       null,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.CallRuleProgram,
       newPasskeyPubkey
     );
@@ -1012,19 +999,14 @@ describe("Test smart wallet with transfer limit", () => {
       createAccount
     );
 
-    const memberSignatureBytes = Buffer.from(
-      newPasskey.sign(message),
-      "base64"
-    );
-
     const executeTxn2 = await lazorkitProgram.executeInstructionTxn(
       newPasskeyPubkey,
-      message,
-      memberSignatureBytes,
+      passkeySigner(newPasskey),
       checkRuleIns,
       createAccount,
       payer.publicKey,
       smartWallet,
+      treasury,
       ExecuteAction.ExecuteCpi
     );
 
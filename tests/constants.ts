@@ -1,6 +1,14 @@
+import { createHash } from "crypto";
+
 export const SMART_WALLET_SEQ_SEED = "smart_wallet_seq";
 export const SMART_WALLET_SEED = "smart_wallet";
 export const SMART_WALLET_CONFIG_SEED = "smart_wallet_config";
 export const WHITELIST_RULE_PROGRAMS_SEED = "whitelist_rule_programs";
 export const RULE_DATA_SEED = "rule_data";
 export const MEMBER_SEED = "member";
+
+// Relying party the test passkeys sign for, accepted by the lazorkit config
+export const TEST_RELYING_PARTY = {
+  rpIdHash: Array.from(createHash("sha256").update("localhost").digest()),
+  origin: "http://localhost:3000",
+};
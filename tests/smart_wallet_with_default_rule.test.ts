@@ -4,13 +4,20 @@ import { expect } from "chai";
 import {
   Keypair,
   LAMPORTS_PER_SOL,
+  PublicKey,
   sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import * as dotenv from "dotenv";
 import { bs58 } from "@coral-xyz/anchor/dist/cjs/utils/bytes";
 import { LazorKitProgram } from "../sdk/lazor-kit";
 import { DefaultRuleProgram } from "../sdk/default-rule-program";
-import { createNewMint, mintTokenTo } from "./utils";
+import {
+  createNewMint,
+  mintTokenTo,
+  passkeySigner,
+  programDataAddress,
+} from "./utils";
+import { TEST_RELYING_PARTY } from "./constants";
 import { createTransferCheckedInstruction } from "@solana/spl-token";
 dotenv.config();
 
@@ -28,6 +35,8 @@ describe.skip("Test smart wallet with default rule", () => {
     bs58.decode(process.env.PRIVATE_KEY!)
   );
 
+  let treasury: PublicKey;
+
   before(async () => {
     // airdrop some SOL to the payer
 
@@ -38,7 +47,9 @@ describe.skip("Test smart wallet with default rule", () => {
     if (smartWalletSeqAccountInfo === null) {
       const txn = await lazorkitProgram.initializeTxn(
         payer.publicKey,
-        defaultRuleProgram.programId
+        defaultRuleProgram.programId,
+        [TEST_RELYING_PARTY],
+        programDataAddress(lazorkitProgram.programId)
       );
 
       await sendAndConfirmTransaction(connection, txn, [payer], {
@@ -46,6 +57,12 @@ describe.skip("Test smart wallet with default rule", () => {
       });
     }
 
+    const lazorkitConfigData =
+      await lazorkitProgram.program.account.config.fetch(
+        lazorkitProgram.config
+      );
+    treasury = lazorkitConfigData.treasury;
+
     const defaultRuleConfigAccountInfo = await connection.getAccountInfo(
       defaultRuleProgram.config
     );
@@ -54,7 +71,8 @@ describe.skip("Test smart wallet with default rule", () => {
       // create the default rule program
       const txn = await defaultRuleProgram.initializeTxn(
         payer.publicKey,
-        lazorkitProgram.authority
+        lazorkitProgram.authority,
+        programDataAddress(defaultRuleProgram.programId)
       );
 
       await sendAndConfirmTransaction(connection, txn, [payer], {
@@ -104,7 +122,8 @@ describe.skip("Test smart wallet with default rule", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -184,7 +203,8 @@ describe.skip("Test smart wallet with default rule", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const createSmartWalletSig = await sendAndConfirmTransaction(
@@ -199,9 +219,6 @@ describe.skip("Test smart wallet with default rule", () => {
 
     console.log("Create smart-wallet: ", createSmartWalletSig);
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const transferSolIns = anchor.web3.SystemProgram.transfer({
       fromPubkey: smartWallet,
       toPubkey: Keypair.generate().publicKey,
@@ -215,12 +232,12 @@ describe.skip("Test smart wallet with default rule", () => {
 
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       checkRule,
       transferSolIns,
       payer.publicKey,
-      smartWallet
+      smartWallet,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -274,7 +291,8 @@ describe.skip("Test smart wallet with default rule", () => {
     const createSmartWalletTxn = await lazorkitProgram.createSmartWalletTxn(
       pubkey,
       initRuleIns,
-      payer.publicKey
+      payer.publicKey,
+      treasury
     );
 
     const createSmartWalletSig = await sendAndConfirmTransaction(
@@ -289,9 +307,6 @@ describe.skip("Test smart wallet with default rule", () => {
 
     console.log("Create smart-wallet: ", createSmartWalletSig);
 
-    const message = Buffer.from("hello");
-    const signatureBytes = Buffer.from(privateKey.sign(message), "base64");
-
     const mint = await createNewMint(connection, payer, 6);
     const smartWalletTokenAccount = await mintTokenTo(
       connection,
@@ -328,12 +343,12 @@ describe.skip("Test smart wallet with default rule", () => {
 
     const executeTxn = await lazorkitProgram.executeInstructionTxn(
       pubkey,
-      message,
-      signatureBytes,
+      passkeySigner(privateKey),
       checkRule,
       transferTokenIns,
       payer.publicKey,
-      smartWallet
+      smartWallet,
+      treasury
     );
 
     const sig = await sendAndConfirmTransaction(
@@ -3,7 +3,15 @@ import {
   getOrCreateAssociatedTokenAccount,
   mintTo,
 } from "@solana/spl-token";
-import { Connection, Keypair, PublicKey, Signer } from "@solana/web3.js";
+import {
+  BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+  Connection,
+  Keypair,
+  PublicKey,
+  Signer,
+} from "@solana/web3.js";
+import { createHash } from "crypto";
+import { TEST_RELYING_PARTY } from "./constants";
 
 export const fundAccountSOL = async (
   connection: Connection,
@@ -77,3 +85,49 @@ export const mintTokenTo = async (
 
   return userTokenAccount.address;
 };
+
+// Program data account holding the upgrade authority checked by `initialize`
+export const programDataAddress = (programId: PublicKey): PublicKey =>
+  PublicKey.findProgramAddressSync(
+    [programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  )[0];
+
+export type PasskeyAssertion = {
+  authenticatorData: Buffer;
+  clientDataJson: Buffer;
+  signature: Buffer;
+};
+
+const FLAG_USER_PRESENT = 0x01;
+const FLAG_USER_VERIFIED = 0x04;
+
+// Signs the challenge of an execute message as a browser passkey would: the
+// signature covers `authenticatorData || sha256(clientDataJSON)`. The sign count
+// stays at zero, as reported by synced passkeys.
+export const passkeySigner =
+  (privateKey: any, relyingParty = TEST_RELYING_PARTY) =>
+  (challenge: Buffer): PasskeyAssertion => {
+    const authenticatorData = Buffer.concat([
+      Buffer.from(relyingParty.rpIdHash),
+      Buffer.from([FLAG_USER_PRESENT | FLAG_USER_VERIFIED]),
+      Buffer.alloc(4),
+    ]);
+    const clientDataJson = Buffer.from(
+      JSON.stringify({
+        type: "webauthn.get",
+        challenge: challenge.toString("base64url"),
+        origin: relyingParty.origin,
+      })
+    );
+    const signature = Buffer.from(
+      privateKey.sign(
+        Buffer.concat([
+          authenticatorData,
+          createHash("sha256").update(clientDataJson).digest(),
+        ])
+      ),
+      "base64"
+    );
+    return { authenticatorData, clientDataJson, signature };
+  };